@@ -0,0 +1,85 @@
+//! Battery levels of connected Bluetooth devices via `bluetoothctl`.
+use anyhow::Result;
+use log::warn;
+
+use super::{BatteryBackend, BatteryStatus};
+use crate::exec::Cmd;
+
+pub struct Bluetoothctl;
+
+impl BatteryBackend for Bluetoothctl {
+    /// Get the battery status of all connected Bluetooth devices that report a battery level.
+    fn batteries(&self) -> Result<Vec<BatteryStatus>> {
+        // Output of the command looks like this:
+        // ```
+        // Device 00:1B:66:AA:BB:CC WH-1000XM4
+        // Device F4:73:35:11:22:33 MX Master 3
+        // ```
+        let output = Cmd::new("bluetoothctl devices Connected")
+            .run_success()?
+            .stdout_str();
+
+        Ok(connected_batteries(&output))
+    }
+}
+
+fn connected_batteries(output: &str) -> Vec<BatteryStatus> {
+    let mut batteries = Vec::new();
+    for line in output.lines() {
+        let mut parts = line.splitn(3, ' ');
+        let (Some("Device"), Some(address), name) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+
+        let info = match Cmd::new(format!("bluetoothctl info {address}")).run_success() {
+            Ok(capture) => capture.stdout_str(),
+            Err(err) => {
+                warn!("Got error on bluetoothctl info call for {address}:\n{err:#?}");
+                continue;
+            }
+        };
+
+        if let Some(battery) = parse_bluetoothctl_info(&info, name.unwrap_or(address)) {
+            batteries.push(battery);
+        }
+    }
+
+    batteries
+}
+
+/// Parse the output of `bluetoothctl info $address`.
+/// Returns `None` if the device doesn't report its battery level.
+///
+/// Output of the command looks like this:
+/// ```text
+/// ...
+/// Alias: WH-1000XM4
+/// RSSI: 0xffffffc2 (-62)
+/// TxPower: 0xfffffff9 (-7)
+/// Battery Percentage: 0x64 (100)
+/// ```
+pub fn parse_bluetoothctl_info(output: &str, fallback_name: &str) -> Option<BatteryStatus> {
+    let mut name = fallback_name.to_string();
+    let mut percentage = None;
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(alias) = line.strip_prefix("Alias:") {
+            name = alias.trim().to_string();
+        } else if let Some(battery) = line.strip_prefix("Battery Percentage:") {
+            // Get the last part in brackets: `(100)`
+            let battery = battery.rsplit('(').next()?.trim_end_matches(')');
+            match battery.parse() {
+                Ok(value) => percentage = Some(value),
+                Err(_) => warn!("Failed to parse battery value to usize: {battery}"),
+            }
+        }
+    }
+
+    // Devices without a battery don't have the battery line at all.
+    percentage.map(|percentage| BatteryStatus {
+        name,
+        percentage: Some(percentage),
+        charging: false,
+    })
+}
@@ -0,0 +1,64 @@
+use std::{fs::read_to_string, path::PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::Backend;
+
+/// The configuration for reading batteries, located at `~/.config/headphone_battery.toml`.
+///
+/// Example:
+/// ```toml
+/// # The backends that're asked for batteries, in this order.
+/// # If a device is reported by several backends, the first one wins.
+/// backends = ["upower", "headsetcontrol"]
+/// # Battery levels in percent at which a notification is sent in `--notify` mode.
+/// notify_thresholds = [20, 10, 5]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatteryConfig {
+    #[serde(default = "default_backends")]
+    pub backends: Vec<Backend>,
+    #[serde(default = "default_notify_thresholds")]
+    pub notify_thresholds: Vec<usize>,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            backends: default_backends(),
+            notify_thresholds: default_notify_thresholds(),
+        }
+    }
+}
+
+/// UPower isn't running everywhere, so it has to be enabled explicitly.
+fn default_backends() -> Vec<Backend> {
+    vec![
+        Backend::Headsetcontrol,
+        Backend::Bluetoothctl,
+        Backend::Sysfs,
+    ]
+}
+
+fn default_notify_thresholds() -> Vec<usize> {
+    vec![15, 5]
+}
+
+impl BatteryConfig {
+    pub fn path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("headphone_battery.toml"))
+    }
+
+    /// Load the config file.
+    /// Returns the default config if there's no config file.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path().filter(|path| path.exists()) else {
+            return Ok(Self::default());
+        };
+
+        let content = read_to_string(&path).context(format!("Failed to read {path:?}"))?;
+        toml::from_str(&content).context(format!("Failed to deserialize {path:?}"))
+    }
+}
@@ -0,0 +1,32 @@
+//! Battery levels of USB headsets via [headsetcontrol](https://github.com/Sapd/HeadsetControl).
+use anyhow::Result;
+
+use super::{BatteryBackend, BatteryStatus};
+use crate::headsetcontrol::{Headset, get_headsets};
+
+pub struct Headsetcontrol;
+
+impl BatteryBackend for Headsetcontrol {
+    fn batteries(&self) -> Result<Vec<BatteryStatus>> {
+        Ok(headset_batteries(&get_headsets()?))
+    }
+}
+
+/// Get the battery status of all headsets that're turned on.
+pub fn headset_batteries(headsets: &[Headset]) -> Vec<BatteryStatus> {
+    headsets
+        .iter()
+        .filter_map(|headset| {
+            let battery = headset
+                .battery
+                .as_ref()
+                .filter(|battery| battery.is_available())?;
+
+            Some(BatteryStatus {
+                name: headset.device.clone(),
+                percentage: battery.percentage(),
+                charging: battery.is_charging(),
+            })
+        })
+        .collect()
+}
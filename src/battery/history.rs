@@ -0,0 +1,222 @@
+//! A small history of battery readings, which is used to estimate how long a battery will last.
+//!
+//! Only changes are recorded, i.e. a new reading is added whenever the level or the charging
+//! state of a device changes.
+use std::{
+    collections::HashMap,
+    fs::{create_dir_all, read_to_string, write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use super::BatteryStatus;
+
+/// Readings older than this are dropped.
+const MAX_AGE_SECONDS: i64 = 24 * 60 * 60;
+/// Only readings of the last few hours are used for the estimate, as the drain changes with usage.
+const ESTIMATE_WINDOW_SECONDS: i64 = 3 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Reading {
+    /// Unix timestamp in seconds.
+    pub timestamp: i64,
+    pub percentage: usize,
+    pub charging: bool,
+}
+
+/// How fast a battery is (dis)charging.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Estimate {
+    /// The change in percent per hour. Always positive.
+    pub rate_per_hour: f64,
+    /// Minutes until the battery is empty, or full while charging.
+    pub minutes_remaining: u64,
+    pub charging: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatteryHistory {
+    devices: HashMap<String, Vec<Reading>>,
+}
+
+impl BatteryHistory {
+    pub fn path() -> Result<PathBuf> {
+        Ok(dirs::data_local_dir()
+            .context("Couldn't find local data dir")?
+            .join("headphone_battery")
+            .join("history.json"))
+    }
+
+    /// Load the history file.
+    /// Missing or broken files just result in an empty history.
+    pub fn load() -> Result<Self> {
+        let content = match read_to_string(Self::path()?) {
+            Ok(content) => content,
+            Err(_) => return Ok(Self::default()),
+        };
+
+        Ok(serde_json::from_str(&content).unwrap_or_else(|err| {
+            debug!("Ignoring broken battery history: {err}");
+            Self::default()
+        }))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent).context(format!("Failed to create {parent:?}"))?;
+        }
+
+        write(&path, serde_json::to_string(self)?).context(format!("Failed to write {path:?}"))
+    }
+
+    /// Record the current batteries and drop old readings.
+    /// Returns whether the history changed.
+    pub fn record(&mut self, batteries: &[BatteryStatus], now: i64) -> bool {
+        let mut history_changed = false;
+        for battery in batteries {
+            let Some(percentage) = battery.percentage else {
+                continue;
+            };
+
+            let readings = self.devices.entry(battery.name.clone()).or_default();
+            let changed = readings.last().is_none_or(|last| {
+                last.percentage != percentage || last.charging != battery.charging
+            });
+            if changed {
+                readings.push(Reading {
+                    timestamp: now,
+                    percentage,
+                    charging: battery.charging,
+                });
+                history_changed = true;
+            }
+        }
+
+        for readings in self.devices.values_mut() {
+            let count = readings.len();
+            readings.retain(|reading| now - reading.timestamp <= MAX_AGE_SECONDS);
+            history_changed |= readings.len() != count;
+        }
+        let count = self.devices.len();
+        self.devices.retain(|_, readings| !readings.is_empty());
+        history_changed |= self.devices.len() != count;
+
+        history_changed
+    }
+
+    /// Estimate the discharge rate and remaining time of a device.
+    ///
+    /// Only the readings since the device started or stopped charging are considered.
+    /// Returns `None` if there aren't enough readings yet.
+    pub fn estimate(&self, name: &str, now: i64) -> Option<Estimate> {
+        let readings = self.devices.get(name)?;
+        let last = readings.last()?;
+
+        let first = readings
+            .iter()
+            .rev()
+            .take_while(|reading| {
+                reading.charging == last.charging
+                    && now - reading.timestamp <= ESTIMATE_WINDOW_SECONDS
+            })
+            .last()?;
+
+        let hours = (last.timestamp - first.timestamp) as f64 / 3600.0;
+        let change = if last.charging {
+            last.percentage as f64 - first.percentage as f64
+        } else {
+            first.percentage as f64 - last.percentage as f64
+        };
+        if hours <= 0.0 || change <= 0.0 {
+            return None;
+        }
+
+        let rate_per_hour = change / hours;
+        let left = if last.charging {
+            100usize.saturating_sub(last.percentage)
+        } else {
+            last.percentage
+        };
+
+        Some(Estimate {
+            rate_per_hour,
+            minutes_remaining: (left as f64 / rate_per_hour * 60.0).round() as u64,
+            charging: last.charging,
+        })
+    }
+}
+
+impl Estimate {
+    /// A short human readable representation, such as `3h 20m left`.
+    pub fn describe(&self) -> String {
+        let hours = self.minutes_remaining / 60;
+        let minutes = self.minutes_remaining % 60;
+        let duration = if hours > 0 {
+            format!("{hours}h {minutes}m")
+        } else {
+            format!("{minutes}m")
+        };
+
+        if self.charging {
+            format!("full in {duration}")
+        } else {
+            format!("{duration} left")
+        }
+    }
+}
+
+/// Record the current batteries in the history file and estimate their remaining time.
+/// The estimates are in the same order as the batteries.
+pub fn record_batteries(batteries: &[BatteryStatus]) -> Result<Vec<Option<Estimate>>> {
+    let now = Utc::now().timestamp();
+    let mut history = BatteryHistory::load()?;
+    if history.record(batteries, now) {
+        history.save()?;
+    }
+
+    Ok(batteries
+        .iter()
+        .map(|battery| history.estimate(&battery.name, now))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battery::tests::headset;
+
+    #[test]
+    fn estimates_remaining_time() {
+        let mut history = BatteryHistory::default();
+        let minute = 60;
+
+        assert!(history.record(&headset(80, false), 0));
+        assert_eq!(history.estimate("Headset", 0), None);
+
+        // Readings without a change aren't recorded.
+        assert!(!history.record(&headset(80, false), 10 * minute));
+        assert!(history.record(&headset(70, false), 60 * minute));
+        assert_eq!(history.devices["Headset"].len(), 2);
+        let estimate = history.estimate("Headset", 60 * minute).unwrap();
+        assert_eq!(estimate.rate_per_hour, 10.0);
+        assert_eq!(estimate.minutes_remaining, 7 * 60);
+        assert_eq!(estimate.describe(), "7h 0m left");
+
+        // Once charging, only the readings since then count.
+        history.record(&headset(70, true), 70 * minute);
+        assert_eq!(history.estimate("Headset", 70 * minute), None);
+        history.record(&headset(85, true), 85 * minute);
+        let estimate = history.estimate("Headset", 85 * minute).unwrap();
+        assert_eq!(estimate.minutes_remaining, 15);
+        assert_eq!(estimate.describe(), "full in 15m");
+
+        // Old readings are dropped.
+        assert!(history.record(&[], 85 * minute + MAX_AGE_SECONDS + 1));
+        assert!(history.devices.is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+//! Battery levels of wireless peripherals, such as headsets, mice, keyboards and controllers.
+use std::fmt;
+
+use anyhow::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+mod bluetoothctl;
+mod config;
+mod headsetcontrol;
+mod history;
+mod sysfs;
+mod upower;
+mod warning;
+
+pub use bluetoothctl::*;
+pub use config::*;
+pub use headsetcontrol::*;
+pub use history::*;
+pub use sysfs::*;
+pub use upower::*;
+pub use warning::*;
+
+/// A source of battery information.
+pub trait BatteryBackend {
+    /// Get the battery status of all devices this backend knows about.
+    fn batteries(&self) -> Result<Vec<BatteryStatus>>;
+}
+
+/// All available backends, as they're named in the config file.
+#[derive(Deserialize, Display, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Backend {
+    Headsetcontrol,
+    Bluetoothctl,
+    Sysfs,
+    Upower,
+}
+
+impl Backend {
+    pub fn backend(&self) -> Box<dyn BatteryBackend> {
+        match self {
+            Backend::Headsetcontrol => Box::new(Headsetcontrol),
+            Backend::Bluetoothctl => Box::new(Bluetoothctl),
+            Backend::Sysfs => Box::new(Sysfs),
+            Backend::Upower => Box::new(Upower),
+        }
+    }
+}
+
+/// The battery status of a single device.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatteryStatus {
+    /// The human readable name of the device.
+    pub name: String,
+    /// The battery level in percent.
+    /// Some devices don't report their level while charging.
+    pub percentage: Option<usize>,
+    pub charging: bool,
+}
+
+impl BatteryStatus {
+    /// The waybar class for this battery.
+    /// The color will change if the battery reaches certain states.
+    pub fn state(&self) -> &'static str {
+        if self.charging {
+            return "good";
+        }
+
+        match self.percentage {
+            Some(0..=15) => "critical",
+            Some(16..=25) => "warning",
+            Some(26..=35) => "normal",
+            _ => "good",
+        }
+    }
+}
+
+impl fmt::Display for BatteryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.percentage, self.charging) {
+            (Some(percentage), true) => write!(f, "{}: {percentage}% (charging)", self.name),
+            (None, true) => write!(f, "{}: charging", self.name),
+            (Some(percentage), false) => write!(f, "{}: {percentage}%", self.name),
+            (None, false) => write!(f, "{}: unknown", self.name),
+        }
+    }
+}
+
+/// Get the battery status of all connected devices from the configured backends.
+///
+/// Devices are identified by their name, so a headset that's reported by several backends only
+/// shows up once. Backends that fail are skipped.
+pub fn get_batteries(config: &BatteryConfig) -> Vec<BatteryStatus> {
+    let mut batteries: Vec<BatteryStatus> = Vec::new();
+    for backend in &config.backends {
+        let found = match backend.backend().batteries() {
+            Ok(found) => found,
+            Err(err) => {
+                warn!("Failed to get batteries from {backend}:\n{err:#?}");
+                continue;
+            }
+        };
+
+        for battery in found {
+            if !batteries
+                .iter()
+                .any(|known| known.name.eq_ignore_ascii_case(&battery.name))
+            {
+                batteries.push(battery);
+            }
+        }
+    }
+
+    batteries
+}
+
+/// The battery that needs attention the most.
+///
+/// That's the discharging battery with the lowest level.
+/// Charging batteries are only considered if there are no others.
+pub fn lowest_battery(batteries: &[BatteryStatus]) -> Option<&BatteryStatus> {
+    batteries
+        .iter()
+        .min_by_key(|battery| (battery.charging, battery.percentage.unwrap_or(100)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir, write};
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn battery(name: &str, percentage: Option<usize>, charging: bool) -> BatteryStatus {
+        BatteryStatus {
+            name: name.to_string(),
+            percentage,
+            charging,
+        }
+    }
+
+    /// A single discharging or charging headset, as most battery tests only need one device.
+    pub(super) fn headset(percentage: usize, charging: bool) -> Vec<BatteryStatus> {
+        vec![battery("Headset", Some(percentage), charging)]
+    }
+
+    #[test]
+    fn lowest_battery_prefers_discharging() {
+        let batteries = vec![
+            battery("Headset", Some(5), true),
+            battery("Mouse", Some(40), false),
+            battery("Keyboard", Some(20), false),
+        ];
+        assert_eq!(lowest_battery(&batteries), Some(&batteries[2]));
+
+        let batteries = vec![battery("Headset", None, true)];
+        assert_eq!(lowest_battery(&batteries), Some(&batteries[0]));
+        assert_eq!(lowest_battery(&[]), None);
+    }
+
+    #[test]
+    fn parses_bluetoothctl_info() {
+        let output = "Device 00:1B:66:AA:BB:CC (public)
+	Name: WH-1000XM4
+	Alias: Sony Headphones
+	Icon: audio-headset
+	Connected: yes
+	Battery Percentage: 0x46 (70)
+";
+        assert_eq!(
+            parse_bluetoothctl_info(output, "fallback"),
+            Some(battery("Sony Headphones", Some(70), false))
+        );
+        assert_eq!(parse_bluetoothctl_info("Name: Mouse", "fallback"), None);
+    }
+
+    #[test]
+    fn reads_peripherals_from_sysfs() -> Result<()> {
+        let dir = TempDir::new()?;
+        let supply = |name: &str, files: &[(&str, &str)]| -> Result<()> {
+            let path = dir.path().join(name);
+            create_dir(&path)?;
+            for (file, content) in files {
+                write(path.join(file), format!("{content}\n"))?;
+            }
+            Ok(())
+        };
+        // The laptop's battery doesn't have a scope.
+        supply("BAT0", &[("capacity", "90"), ("status", "Discharging")])?;
+        supply(
+            "hidpp_battery_0",
+            &[
+                ("scope", "Device"),
+                ("model_name", "MX Master 3"),
+                ("capacity", "30"),
+                ("status", "Discharging"),
+            ],
+        )?;
+        supply(
+            "sony_controller_battery_00:1b:66:aa:bb:cc",
+            &[
+                ("scope", "Device"),
+                ("capacity_level", "Low"),
+                ("status", "Charging"),
+            ],
+        )?;
+
+        assert_eq!(
+            read_power_supplies(dir.path())?,
+            vec![
+                battery("MX Master 3", Some(30), false),
+                battery("sony_controller_battery_00:1b:66:aa:bb:cc", Some(20), true),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parses_upower_properties() -> Result<()> {
+        let paths = parse_device_paths(
+            r#"{"type":"ao","data":[["/org/freedesktop/UPower/devices/battery_BAT0","/org/freedesktop/UPower/devices/headset_dev_00_1B_66_AA_BB_CC"]]}"#,
+        )?;
+        assert_eq!(paths.len(), 2);
+
+        let headset = r#"{"type":"s","data":"WH-1000XM4"}
+{"type":"u","data":17}
+{"type":"b","data":false}
+{"type":"b","data":true}
+{"type":"d","data":60.0}
+{"type":"u","data":2}"#;
+        assert_eq!(
+            parse_device_properties(headset)?,
+            Some(battery("WH-1000XM4", Some(60), false))
+        );
+
+        // The laptop's own battery powers the system.
+        let laptop = r#"{"type":"s","data":"5B10W13930"}
+{"type":"u","data":2}
+{"type":"b","data":true}
+{"type":"b","data":true}
+{"type":"d","data":80.0}
+{"type":"u","data":1}"#;
+        assert_eq!(parse_device_properties(laptop)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn converts_headsetcontrol_batteries() -> Result<()> {
+        let output = crate::headsetcontrol::parse_headsetcontrol_json(include_str!(
+            "../headsetcontrol/fixtures/multiple.json"
+        ))?;
+
+        // The second headset doesn't report its battery.
+        assert_eq!(
+            headset_batteries(&output.devices),
+            vec![battery("Logitech G PRO X Wireless", Some(42), false)]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+//! Battery levels of peripherals that're exposed by the kernel in `/sys/class/power_supply`.
+//!
+//! Many HID devices, such as Logitech mice or game controllers, have kernel drivers that report
+//! their battery. These entries have a `scope` of `Device`, in contrast to the system's battery.
+use std::{
+    fs::{read_dir, read_to_string},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+
+use super::{BatteryBackend, BatteryStatus};
+
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+pub struct Sysfs;
+
+impl BatteryBackend for Sysfs {
+    fn batteries(&self) -> Result<Vec<BatteryStatus>> {
+        read_power_supplies(Path::new(POWER_SUPPLY_DIR))
+    }
+}
+
+/// Read all peripheral batteries from a `power_supply` class directory.
+pub fn read_power_supplies(dir: &Path) -> Result<Vec<BatteryStatus>> {
+    let mut batteries = Vec::new();
+    if !dir.exists() {
+        return Ok(batteries);
+    }
+
+    let entries = read_dir(dir).context(format!("Failed to read {dir:?}"))?;
+    for entry in entries {
+        let path = entry?.path();
+        if let Some(battery) = read_power_supply(&path) {
+            batteries.push(battery);
+        }
+    }
+    batteries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(batteries)
+}
+
+/// Read a single power supply.
+/// Returns `None` if it isn't a peripheral or doesn't report its battery.
+fn read_power_supply(path: &Path) -> Option<BatteryStatus> {
+    let read = |name: &str| {
+        read_to_string(path.join(name))
+            .ok()
+            .map(|value| value.trim().to_string())
+    };
+
+    if read("scope")?.as_str() != "Device" {
+        return None;
+    }
+
+    // Some drivers only report a rough level instead of the exact capacity.
+    let percentage = match read("capacity") {
+        Some(capacity) => capacity.parse().ok(),
+        None => read("capacity_level").and_then(|level| match level.as_str() {
+            "Full" => Some(100),
+            "High" => Some(80),
+            "Normal" => Some(50),
+            "Low" => Some(20),
+            "Critical" => Some(5),
+            _ => None,
+        }),
+    };
+    let charging = read("status").is_some_and(|status| status == "Charging");
+    if percentage.is_none() && !charging {
+        return None;
+    }
+
+    let name = read("model_name")
+        .filter(|name| !name.is_empty())
+        .or_else(|| Some(path.file_name()?.to_string_lossy().to_string()))?;
+
+    Some(BatteryStatus {
+        name,
+        percentage,
+        charging,
+    })
+}
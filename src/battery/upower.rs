@@ -0,0 +1,114 @@
+//! Battery levels of all devices UPower knows about, queried via D-Bus with `busctl`.
+use anyhow::{Context, Result, bail};
+use log::{debug, warn};
+use serde::Deserialize;
+
+use super::{BatteryBackend, BatteryStatus};
+use crate::exec::Cmd;
+
+/// The properties we read from each device, in this order.
+const PROPERTIES: &[&str] = &[
+    "Model",
+    "Type",
+    "PowerSupply",
+    "IsPresent",
+    "Percentage",
+    "State",
+];
+
+/// `Type` of devices that don't have a battery.
+const TYPE_LINE_POWER: u64 = 1;
+/// `State` of devices that're currently charging.
+const STATE_CHARGING: u64 = 1;
+
+pub struct Upower;
+
+impl BatteryBackend for Upower {
+    fn batteries(&self) -> Result<Vec<BatteryStatus>> {
+        let capture = Cmd::new(
+            "busctl --system --json=short call org.freedesktop.UPower /org/freedesktop/UPower \
+            org.freedesktop.UPower EnumerateDevices",
+        )
+        .run_success()?;
+        let devices = parse_device_paths(&capture.stdout_str())?;
+
+        let mut batteries = Vec::new();
+        for path in devices {
+            let capture = match Cmd::new(format!(
+                "busctl --system --json=short get-property org.freedesktop.UPower {path} \
+                org.freedesktop.UPower.Device {}",
+                PROPERTIES.join(" ")
+            ))
+            .run_success()
+            {
+                Ok(capture) => capture,
+                Err(err) => {
+                    warn!("Failed to get properties of UPower device {path}:\n{err:#?}");
+                    continue;
+                }
+            };
+
+            match parse_device_properties(&capture.stdout_str()) {
+                Ok(Some(battery)) => batteries.push(battery),
+                Ok(None) => debug!("Skipping UPower device {path}"),
+                Err(err) => warn!("Failed to read UPower device {path}:\n{err:#?}"),
+            }
+        }
+
+        Ok(batteries)
+    }
+}
+
+/// A single value of a `busctl --json=short` reply.
+#[derive(Deserialize)]
+struct BusValue<T> {
+    data: T,
+}
+
+/// Parse the reply of `EnumerateDevices`, which looks like this:
+/// `{"type":"ao","data":[["/org/freedesktop/UPower/devices/battery_BAT0"]]}`
+pub fn parse_device_paths(output: &str) -> Result<Vec<String>> {
+    let reply: BusValue<Vec<Vec<String>>> =
+        serde_json::from_str(output).context("Failed to parse UPower devices")?;
+
+    Ok(reply.data.into_iter().flatten().collect())
+}
+
+/// Parse the reply of `get-property`, which contains one JSON value per property and line.
+/// Returns `None` for devices that power the system itself or don't have a battery.
+pub fn parse_device_properties(output: &str) -> Result<Option<BatteryStatus>> {
+    let values = output
+        .lines()
+        .map(serde_json::from_str::<BusValue<serde_json::Value>>)
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to parse UPower device properties")?;
+    let [
+        model,
+        device_type,
+        power_supply,
+        is_present,
+        percentage,
+        state,
+    ] = values.as_slice()
+    else {
+        bail!("Expected {} UPower properties", PROPERTIES.len());
+    };
+
+    if power_supply.data.as_bool() == Some(true)
+        || is_present.data.as_bool() == Some(false)
+        || device_type.data.as_u64() == Some(TYPE_LINE_POWER)
+    {
+        return Ok(None);
+    }
+
+    let name = model.data.as_str().unwrap_or_default();
+    if name.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(BatteryStatus {
+        name: name.to_string(),
+        percentage: percentage.data.as_f64().map(|value| value.round() as usize),
+        charging: state.data.as_u64() == Some(STATE_CHARGING),
+    }))
+}
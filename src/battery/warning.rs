@@ -0,0 +1,157 @@
+//! Warn about devices that're about to run out of battery.
+//!
+//! We only warn once per threshold. The warnings a device already got are remembered in the
+//! runtime dir, so they survive restarts of the status bar, and are reset once the device is
+//! charged again.
+use std::{
+    collections::HashMap,
+    fs::{read_to_string, write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use dirs::runtime_dir;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use super::BatteryStatus;
+use crate::notify::critical_notify;
+
+/// Warnings are reset once a device is this many percent above the threshold it's been warned
+/// about. That way, fluctuating levels don't result in repeated warnings.
+const HYSTERESIS: usize = 10;
+
+/// The lowest threshold each device has been warned about.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WarningState {
+    warned: HashMap<String, usize>,
+}
+
+impl WarningState {
+    pub fn path() -> Result<PathBuf> {
+        Ok(runtime_dir()
+            .context("Couldn't find runtime dir")?
+            .join("headphone_battery-warnings.json"))
+    }
+
+    /// Load the state from the runtime dir.
+    /// Missing or broken state files just result in a fresh state.
+    pub fn load() -> Result<Self> {
+        let content = match read_to_string(Self::path()?) {
+            Ok(content) => content,
+            Err(_) => return Ok(Self::default()),
+        };
+
+        Ok(serde_json::from_str(&content).unwrap_or_else(|err| {
+            debug!("Ignoring broken warning state: {err}");
+            Self::default()
+        }))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        write(&path, serde_json::to_string(self)?).context(format!("Failed to write {path:?}"))
+    }
+
+    /// Update the state with the current batteries.
+    /// Returns all batteries that crossed a new threshold, together with that threshold.
+    pub fn update<'a>(
+        &mut self,
+        batteries: &'a [BatteryStatus],
+        thresholds: &[usize],
+    ) -> Vec<(&'a BatteryStatus, usize)> {
+        let mut warnings = Vec::new();
+        for battery in batteries {
+            if battery.charging {
+                self.warned.remove(&battery.name);
+                continue;
+            }
+            let Some(percentage) = battery.percentage else {
+                continue;
+            };
+
+            let warned = self.warned.get(&battery.name).copied();
+            if warned.is_some_and(|warned| percentage > warned + HYSTERESIS) {
+                self.warned.remove(&battery.name);
+            }
+
+            // Only the lowest threshold counts, if we skipped several at once.
+            let Some(threshold) = thresholds
+                .iter()
+                .copied()
+                .filter(|threshold| percentage <= *threshold)
+                .min()
+            else {
+                continue;
+            };
+
+            if self
+                .warned
+                .get(&battery.name)
+                .is_some_and(|warned| *warned <= threshold)
+            {
+                continue;
+            }
+
+            self.warned.insert(battery.name.clone(), threshold);
+            warnings.push((battery, threshold));
+        }
+
+        warnings
+    }
+}
+
+/// Send a notification for each battery that crossed one of the thresholds since the last check.
+pub fn notify_low_batteries(batteries: &[BatteryStatus], thresholds: &[usize]) -> Result<()> {
+    let mut state = WarningState::load()?;
+    let warnings = state.update(batteries, thresholds);
+    for (battery, _) in &warnings {
+        critical_notify(10000, format!("Low battery: {battery}"))?;
+    }
+
+    state.save()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battery::tests::headset;
+
+    fn thresholds_of(warnings: Vec<(&BatteryStatus, usize)>) -> Vec<usize> {
+        warnings
+            .into_iter()
+            .map(|(_, threshold)| threshold)
+            .collect()
+    }
+
+    #[test]
+    fn warns_once_per_threshold() {
+        let thresholds = [15, 5];
+        let mut state = WarningState::default();
+
+        assert!(state.update(&headset(50, false), &thresholds).is_empty());
+        assert_eq!(
+            thresholds_of(state.update(&headset(15, false), &thresholds)),
+            [15]
+        );
+        assert!(state.update(&headset(14, false), &thresholds).is_empty());
+
+        // Fluctuating levels don't result in new warnings.
+        assert!(state.update(&headset(17, false), &thresholds).is_empty());
+        assert!(state.update(&headset(15, false), &thresholds).is_empty());
+
+        // Skipping a threshold only results in a single warning.
+        assert_eq!(
+            thresholds_of(state.update(&headset(3, false), &thresholds)),
+            [5]
+        );
+        assert!(state.update(&headset(15, false), &thresholds).is_empty());
+
+        // Charging resets the warnings.
+        assert!(state.update(&headset(30, true), &thresholds).is_empty());
+        assert_eq!(
+            thresholds_of(state.update(&headset(15, false), &thresholds)),
+            [15]
+        );
+    }
+}
@@ -1,15 +1,34 @@
 //! Small convenience script to quickly change the output sink.
 //! It also moves all current outputs to that sink as well.
 //!
+//! The same can be done for microphones via the `source` subcommand.
+//!
+//! Single applications can be moved to another sink via `move-stream`.
+//! Applications that should always use a specific sink, ignored sinks, aliases and the order in
+//! which sinks are rotated can be configured in `~/.config/change_sink.toml`, see [`SinkConfig`].
+//!
+//! `list --format json|dmenu` and `menu` allow picking a sink via launchers such as rofi or fuzzel.
+//! The selection can be passed back via `target --id`.
+//!
+//! Device profiles, such as A2DP and HSP/HFP of Bluetooth headsets, can be changed via `profile`.
+//!
+//! `watch` runs as a daemon and automatically switches to a sink once it's plugged in, if it
+//! comes before the current sink in the configured `order`.
+//!
 //! This is currently used by me via shortcuts.
 //! Needed binaries:
 //! - pw-dump
-//! - pactl
-use anyhow::Result;
+//! - pw-metadata
+//! - wpctl
+use anyhow::{Context, Result, bail};
 use clap::{ArgAction, Parser};
 use script_utils::{exec::Cmd, logging, notify::*, pipewire::*};
 use strum::Display;
 
+/// Rofi shows the description column only, the id is only used to find the selected sink.
+const DEFAULT_LAUNCHER: &str =
+    "rofi -dmenu -i -p sink -display-columns 1 -display-column-separator '\\t'";
+
 #[derive(Parser, Debug)]
 #[clap(
     name = "change_sink",
@@ -34,11 +53,70 @@ pub enum Command {
     Previous,
     // Switch to the default built-in device.
     BuiltIn,
-    // Switch to a specific target
-    Target { target: String },
+    // Switch to a specific target by its alias or description.
+    // Use `--id` to select it by the id of `list --format json|dmenu` instead.
+    Target {
+        #[clap(required_unless_present = "id")]
+        target: Option<String>,
+        #[clap(long, conflicts_with = "target")]
+        id: Option<String>,
+    },
     // List all devices
+    List {
+        #[clap(short, long, value_enum, default_value_t = ListFormat::Plain)]
+        format: ListFormat,
+    },
+    // Select a device in a dmenu-like launcher, which gets the entries via stdin
+    Menu {
+        #[clap(short, long, default_value = DEFAULT_LAUNCHER)]
+        launcher: String,
+    },
+    // List all applications that're playing audio
+    Streams,
+    // Move all streams of a single application to a specific target
+    MoveStream {
+        application: String,
+        target: String,
+    },
+    // Move applications to their sinks according to the routing rules in the config file
+    ApplyRoutes,
+    // Keep running and switch to sinks with a higher priority as soon as they're plugged in
+    Watch,
+    // Switch the microphone instead of the output device
+    Source {
+        #[command(subcommand)]
+        command: SourceCommand,
+    },
+    // Change the profiles of audio devices
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+}
+
+#[derive(Parser, Display, Clone, Debug, PartialEq)]
+pub enum SourceCommand {
+    // Go to the next microphone
+    Next,
+    // Go to the previous microphone
+    Previous,
+    // Switch to a specific microphone
+    Target { target: String },
+    // List all microphones
     List,
 }
+
+#[derive(Parser, Display, Clone, Debug, PartialEq)]
+pub enum ProfileCommand {
+    // List the profiles of all devices or a specific device
+    List { device: Option<String> },
+    // Set the profile of a device by the profile's index or name
+    Set { device: String, profile: String },
+    // Toggle between playback (A2DP) and headset (HSP/HFP) mode of a Bluetooth headset.
+    // This also switches the default sink and source.
+    // Defaults to the first device that supports headset mode.
+    Headset { device: Option<String> },
+}
 fn main() -> Result<()> {
     // Parse commandline options.
     let args = CliArguments::parse();
@@ -47,16 +125,35 @@ fn main() -> Result<()> {
     let device = match args.command {
         Command::Next => rotate_sink(Direction::Next)?,
         Command::Previous => rotate_sink(Direction::Previous)?,
-        Command::BuiltIn => get_sinks()?
-            .into_iter()
-            .find(|device| device.info.props.node_description.starts_with("Built-in")),
-        Command::Target { ref target } => get_sinks()?
-            .into_iter()
-            .find(|device| device.info.props.node_description.starts_with(target)),
-        Command::List => {
-            list_sinks()?;
+        Command::BuiltIn => find_built_in_sink(&SinkConfig::load()?, &get_sinks()?).cloned(),
+        Command::Target {
+            target: Some(ref target),
+            ..
+        } => find_sink(&SinkConfig::load()?, &get_sinks()?, target).cloned(),
+        Command::Target {
+            id: Some(ref id), ..
+        } => find_node_by_id(&get_sinks()?, id).cloned(),
+        Command::Target { .. } => None,
+        Command::List { format } => return list_sinks(format),
+        Command::Menu { ref launcher } => {
+            let sinks = get_sinks()?;
+            let Some(selection) = select_in_launcher(launcher, &sinks)? else {
+                return Ok(());
+            };
+            find_node_by_id(&sinks, &selection).cloned()
+        }
+        Command::Streams => {
+            list_streams()?;
             return Ok(());
         }
+        Command::MoveStream {
+            ref application,
+            ref target,
+        } => return move_stream(application, target),
+        Command::ApplyRoutes => return apply_routes(),
+        Command::Watch => return watch_sinks(),
+        Command::Source { ref command } => return handle_source_command(command),
+        Command::Profile { ref command } => return handle_profile_command(command),
     };
 
     let Some(device) = device else {
@@ -71,37 +168,213 @@ fn main() -> Result<()> {
         return Ok(());
     };
 
-    switch_device(&device)?;
+    switch_sink(&device)?;
+
+    Ok(())
+}
+
+/// Print all sinks in the given format.
+fn list_sinks(format: ListFormat) -> Result<()> {
+    let sinks = get_sinks()?;
+    match format {
+        ListFormat::Plain => list_nodes(NodeKind::Sink, sinks),
+        ListFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&menu_entries(&sinks)?)?)
+        }
+        ListFormat::Dmenu => {
+            for entry in menu_entries(&sinks)? {
+                println!("{}", entry.dmenu_line());
+            }
+        }
+    }
 
     Ok(())
 }
 
-/// Set the target device as the default sink.
-/// Also take all inputs that're currently open and move them over to the target device.
-/// This allows for a clean transition of any active streams when switching devices.
-fn switch_device(node: &Node) -> Result<()> {
-    let props = &node.info.props;
-    // Set the default sink.
-    Cmd::new(format!("wpctl set-default {}", props.object_id)).run_success()?;
+fn menu_entries(sinks: &[Node]) -> Result<Vec<MenuEntry>> {
+    let config = SinkConfig::load()?;
+    let default_name = get_default_node_name(NodeKind::Sink).ok();
+
+    Ok(sinks
+        .iter()
+        .map(|sink| MenuEntry::new(sink, &config, default_name.as_deref()))
+        .collect())
+}
+
+/// Let the user pick one of the sinks in the launcher.
+/// Returns `None` if the selection has been aborted.
+fn select_in_launcher(launcher: &str, sinks: &[Node]) -> Result<Option<String>> {
+    let input = menu_entries(sinks)?
+        .iter()
+        .map(MenuEntry::dmenu_line)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // Launchers exit with a non-zero exit code if nothing has been selected.
+    // They also like to print warnings, which must not end up in the selection.
+    let capture = Cmd::new(launcher).stdin(input).separate_stderr().run()?;
+    if !capture.exit_status.success() {
+        return Ok(None);
+    }
+
+    Ok(Some(capture.stdout_str()))
+}
+
+/// Move a single application over to the target sink.
+fn move_stream(application: &str, target: &str) -> Result<()> {
+    let config = SinkConfig::load()?;
+    let sinks = get_sinks()?;
+    let Some(sink) = find_sink(&config, &sinks, target) else {
+        critical_notify(1500, format!("Could not find target sink: {target}"))?;
+        return Ok(());
+    };
 
-    move_inputs_to_sink(props.object_serial)?;
+    let moved = move_application_to_sink(application, sink)?;
+    if moved == 0 {
+        critical_notify(1500, format!("Could not find any stream of {application}"))?;
+        return Ok(());
+    }
 
-    // Inform the user about the sink we just switched to.
-    notify(1500, format!("Changed sink to {}", props.node_description))?;
+    notify(
+        1500,
+        format!("Moved {application} to {}", config.display_name(sink)),
+    )?;
 
     Ok(())
 }
 
-/// Get the list of all active sinks and print them to the commandline.
-fn list_sinks() -> Result<()> {
-    let nodes = get_sinks()?;
+/// Print all applications that're playing audio and the sink they're connected to.
+fn list_streams() -> Result<()> {
+    let streams = get_output_streams()?;
+    if streams.is_empty() {
+        println!("Found no streams");
+        return Ok(());
+    }
+
+    let sinks = get_sinks()?;
+    println!("Found the following streams:");
+    for output_stream in streams.iter() {
+        let stream = &output_stream.stream;
+        let target = output_stream
+            .target
+            .and_then(|id| sinks.iter().find(|sink| sink.id == id))
+            .map(|sink| sink.info.props.node_description.as_str())
+            .unwrap_or("unknown");
+
+        println!(
+            "{}:\n \
+            Application: {}\n \
+            Media: {}\n \
+            Target: {}\n \
+            ",
+            stream.id,
+            stream.application(),
+            stream.info.props.media_name.as_deref().unwrap_or_default(),
+            target,
+        );
+    }
+
+    Ok(())
+}
+
+/// Switch the microphone the same way we switch sinks.
+fn handle_source_command(command: &SourceCommand) -> Result<()> {
+    let device = match command {
+        SourceCommand::Next => rotate_source(Direction::Next)?,
+        SourceCommand::Previous => rotate_source(Direction::Previous)?,
+        SourceCommand::Target { target } => get_sources()?
+            .into_iter()
+            .find(|device| device.info.props.node_description.starts_with(target)),
+        SourceCommand::List => {
+            list_nodes(NodeKind::Source, get_sources()?);
+            return Ok(());
+        }
+    };
+
+    let Some(device) = device else {
+        critical_notify(
+            1500,
+            format!("Could not find target source for command: {command:#?}"),
+        )?;
 
-    if nodes.is_empty() {
-        println!("Found no sinks");
         return Ok(());
+    };
+
+    switch_source(&device)
+}
+
+/// List or change device profiles.
+fn handle_profile_command(command: &ProfileCommand) -> Result<()> {
+    let devices = get_audio_devices()?;
+    let find = |target: &str| {
+        find_device(&devices, target).with_context(|| format!("Could not find device {target}"))
+    };
+
+    match command {
+        ProfileCommand::List { device } => {
+            let devices = match device {
+                Some(target) => vec![find(target)?.clone()],
+                None => devices.clone(),
+            };
+            list_profiles(&devices);
+        }
+        ProfileCommand::Set { device, profile } => {
+            let device = find(device)?;
+            let Some(profile) = find_profile(device, profile) else {
+                bail!(
+                    "Could not find profile {profile} for {}",
+                    device.info.props.device_description
+                );
+            };
+            set_profile(device, profile)?;
+        }
+        ProfileCommand::Headset { device } => {
+            let device = match device {
+                Some(target) => find(target)?,
+                None => devices
+                    .iter()
+                    .find(|device| headset_toggle_profile(device).is_some())
+                    .context("Could not find any device that supports headset mode")?,
+            };
+            toggle_headset_mode(device)?;
+        }
     }
 
-    println!("Found the following sinks:");
+    Ok(())
+}
+
+/// Print all profiles of the given devices. The active profile is marked with a `*`.
+fn list_profiles(devices: &[Device]) {
+    for device in devices {
+        let props = &device.info.props;
+        println!(
+            "{}: {} ({})",
+            device.id, props.device_description, props.device_name
+        );
+
+        let active = device.active_profile().map(|profile| profile.index);
+        for profile in &device.info.params.profiles {
+            let marker = if Some(profile.index) == active {
+                "*"
+            } else {
+                " "
+            };
+            println!(
+                " {marker} {}: {} - {} (available: {})",
+                profile.index, profile.name, profile.description, profile.available
+            );
+        }
+    }
+}
+
+/// Print the given list of active nodes to the commandline.
+fn list_nodes(kind: NodeKind, nodes: Vec<Node>) {
+    if nodes.is_empty() {
+        println!("Found no {kind}s");
+        return;
+    }
+
+    println!("Found the following {kind}s:");
     for node in nodes.iter() {
         let props = &node.info.props;
         println!(
@@ -113,6 +386,4 @@ fn list_sinks() -> Result<()> {
             props.object_id, props.object_serial, props.node_description, props.node_name,
         );
     }
-
-    Ok(())
 }
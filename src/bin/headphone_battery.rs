@@ -1,9 +1,37 @@
 //! Small helper script to get the battery status of my various wireless headphones.
+//!
+//! All connected devices with a battery, such as mice and controllers, are reported as well.
+//! The bar shows the lowest battery, the tooltip lists all of them.
+//!
+//! Readings are recorded in a history file, which is used to estimate the remaining time.
+//! `--json` prints all batteries together with these estimates.
+//!
+//! With `--notify`, a critical notification is sent once a device drops below a threshold.
+//!
+//! Batteries are read from `headsetcontrol`, `bluetoothctl`, `/sys/class/power_supply` and UPower.
+//! Which of them are used, and in which order, can be configured in
+//! `~/.config/headphone_battery.toml`, see [`BatteryConfig`].
+
+use std::time::Duration;
 
 use anyhow::Result;
 use clap::{ArgAction, Parser};
 use log::warn;
-use script_utils::{exec::Cmd, i3status::CustomBarStatus, logging};
+use script_utils::{
+    battery::{
+        BatteryConfig,
+        BatteryStatus,
+        Estimate,
+        get_batteries,
+        lowest_battery,
+        notify_low_batteries,
+        record_batteries,
+    },
+    i3status::{BarFormat, CustomBarStatus, MouseButton, StatusLoop, StatusPrinter},
+    logging,
+    pipewire::{Direction, rotate_sink, switch_sink},
+};
+use serde::Serialize;
 
 #[derive(Parser, Debug)]
 #[clap(
@@ -15,173 +43,131 @@ struct CliArguments {
     /// Verbose mode (-v, -vv, -vvv)
     #[clap(short, long, action = ArgAction::Count)]
     pub verbose: u8,
+
+    /// The output format.
+    #[clap(short, long, value_enum, default_value_t = BarFormat::Waybar)]
+    pub format: BarFormat,
+
+    /// Keep running and print a new line whenever the status changes.
+    #[clap(short, long)]
+    pub watch: bool,
+
+    /// The refresh interval (in seconds) in watch mode.
+    #[clap(short, long, default_value = "30")]
+    pub interval: u64,
+
+    /// Handle a click on the module and exit.
+    /// Meant to be used in waybar's `on-click` options.
+    #[clap(short, long, value_enum)]
+    pub click: Option<MouseButton>,
+
+    /// Send a notification once a device's battery drops below one of the configured thresholds.
+    #[clap(short, long)]
+    pub notify: bool,
+
+    /// Print all batteries with their estimated remaining time as JSON and exit.
+    #[clap(short, long)]
+    pub json: bool,
+}
+
+/// A battery and its estimate for the JSON output.
+#[derive(Serialize)]
+struct BatteryReport<'a> {
+    #[serde(flatten)]
+    battery: &'a BatteryStatus,
+    estimate: Option<Estimate>,
 }
 
 fn main() -> Result<()> {
     // Parse commandline options.
     let args = CliArguments::parse();
     logging::init_logger(args.verbose);
+    let mut printer = StatusPrinter::new(args.format);
 
-    // Check headsetcontrol first
-    let mut device_status = headsetcontrol();
+    if let Some(button) = args.click {
+        return handle_click(button);
+    }
 
-    // Check bluetoothctl next.
-    if device_status == DeviceStatus::Unavailable {
-        device_status = bluetoothctl();
+    let config = BatteryConfig::load()?;
+    if args.json {
+        let batteries = get_batteries(&config);
+        let reports: Vec<BatteryReport> = batteries
+            .iter()
+            .zip(estimates(&batteries))
+            .map(|(battery, estimate)| BatteryReport { battery, estimate })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+        return Ok(());
     }
 
-    // If we got some headphone info, format and print it.
-    let state = state_from_battery_status(&device_status);
-
-    let inner_text = match device_status {
-        DeviceStatus::Charging { percentage } => {
-            if let Some(percentage) = percentage {
-                format!("{percentage}% ")
-            } else {
-                "".to_string()
-            }
-        }
-        DeviceStatus::Available { percentage } => format!("{percentage}%"),
-        DeviceStatus::Unavailable => {
-            // We didn't get any info, return an empty response.
-            println!("{}", serde_json::to_string(&CustomBarStatus::default())?);
-            return Ok(());
-        }
-    };
+    if args.watch {
+        let interval = Duration::from_secs(args.interval);
+        return StatusLoop::new(printer, interval)
+            .on_click(|event| match event.mouse_button() {
+                Some(button) => handle_click(button),
+                None => Ok(()),
+            })
+            .run(move || battery_status(&config, args.notify));
+    }
 
-    let text = format!("( {inner_text})");
-    let mut status = CustomBarStatus::new(text);
-    status.class = state.into();
-    let json = serde_json::to_string(&status)?;
-    println!("{json}");
+    printer.print(&battery_status(&config, args.notify)?)?;
 
     Ok(())
 }
 
-#[derive(PartialEq)]
-enum DeviceStatus {
-    Charging { percentage: Option<usize> },
-    Available { percentage: usize },
-    Unavailable,
-}
-
-/// Determine the i3status state for this section.
-/// The color will change if the battery reaches certain states.
-fn state_from_battery_status(battery_status: &DeviceStatus) -> &str {
-    match battery_status {
-        DeviceStatus::Charging { .. } => "good",
-        DeviceStatus::Available { percentage } => match percentage {
-            0..=15 => "critical",
-            16..=25 => "warning",
-            26..=35 => "normal",
-            _ => "good",
-        },
-        _ => "good",
+/// Switch to the next sink on left click.
+fn handle_click(button: MouseButton) -> Result<()> {
+    if button != MouseButton::Left {
+        return Ok(());
     }
-}
-
-// First check `headsetcontrol`.
-// <https://github.com/Sapd/HeadsetControl>
-fn headsetcontrol() -> DeviceStatus {
-    let result = Cmd::new("headsetcontrol --battery").run_success();
-    let output = match result {
-        Ok(capture) => capture.stdout_str(),
-        Err(err) => {
-            warn!("Got error on headsetcontrol call:\n{err:#?}");
-            return DeviceStatus::Unavailable;
-        }
-    };
 
-    enum Availablility {
-        Charging,
-        Available,
-        Unavailable,
+    if let Some(node) = rotate_sink(Direction::Next)? {
+        switch_sink(&node)?;
     }
 
-    // Output looks like this:
-    // ```
-    // Found SteelSeries Arctis Nova 7 (Arctis Nova 7)!
-    //
-    // Battery:
-    // 	      Status: BATTERY_AVAILABLE
-    // 	      Level: 100%
-    // ```
-    let mut availability = Availablility::Unavailable;
-    for line in output.lines() {
-        let line = line.trim();
-        if line.starts_with("Status:") {
-            let parts: Vec<String> = line.split(' ').map(|s| s.to_string()).collect();
-            let status_str = &parts[1];
-
-            availability = match status_str.as_str() {
-                "BATTERY_CHARGING" => Availablility::Charging,
-                "BATTERY_AVAILABLE" => Availablility::Available,
-                _ => Availablility::Unavailable,
-            };
-        }
-
-        // Battery status of the command starts with "Level:"
-        if line.starts_with("Level:") {
-            let parts: Vec<String> = line.split(' ').map(|s| s.to_string()).collect();
-            let battery = &parts[1];
-
-            // Remove the percentage sign
-            let Ok(percentage) = battery.trim_end_matches('%').parse() else {
-                warn!("Failed to parse battery value to usize: {battery}");
-                return DeviceStatus::Unavailable;
-            };
-
-            let status = match availability {
-                Availablility::Charging => DeviceStatus::Charging {
-                    percentage: Some(percentage),
-                },
-                Availablility::Available => DeviceStatus::Available { percentage },
-                Availablility::Unavailable => DeviceStatus::Unavailable,
-            };
-
-            return status;
-        }
+    Ok(())
+}
+
+/// Get the battery status of all devices we can find.
+/// The device with the lowest battery is shown in the bar, all devices are listed in the tooltip.
+fn battery_status(config: &BatteryConfig, notify: bool) -> Result<CustomBarStatus> {
+    let batteries = get_batteries(config);
+    if notify && let Err(err) = notify_low_batteries(&batteries, &config.notify_thresholds) {
+        warn!("Failed to notify about low batteries:\n{err:#?}");
     }
 
-    DeviceStatus::Unavailable
-}
+    // We didn't get any info, return an empty response.
+    let Some(lowest) = lowest_battery(&batteries) else {
+        return Ok(CustomBarStatus::default());
+    };
 
-// Check `bluetoothctl` for any devices with a battery.
-//
-// This is a pretty stupid check and would theoretically detect any device with a bettery, but it's
-// good enough for me.
-fn bluetoothctl() -> DeviceStatus {
-    let result = Cmd::new("bluetoothctl info").run_success();
-    let output = match result {
-        Ok(capture) => capture.stdout_str(),
-        Err(err) => {
-            warn!("Got error on bluetoothctl call:\n{err:#?}");
-            return DeviceStatus::Unavailable;
-        }
+    let inner_text = match lowest.percentage {
+        Some(percentage) if lowest.charging => format!("{percentage}% "),
+        Some(percentage) => format!("{percentage}%"),
+        None => "".to_string(),
     };
 
-    // Output of the command looks like this:
-    // ```
-    // ...
-    // RSSI: 0xffffffc2 (-62)
-    // TxPower: 0xfffffff9 (-7)
-    // Battery Percentage: 0x64 (100)
-    // ```
-    for line in output.lines() {
-        if line.trim().starts_with("Battery Percentage:") {
-            // Split at `(` to get the last part: `100)`
-            let parts: Vec<String> = line.split('(').map(|s| s.to_string()).collect();
-            let battery = &parts[1];
-
-            // Remove the closing bracket
-            let Ok(percentage) = battery.trim_end_matches(')').parse() else {
-                warn!("Failed to parse battery value to usize: {battery}");
-                return DeviceStatus::Unavailable;
-            };
-
-            return DeviceStatus::Available { percentage };
-        }
-    }
+    let text = format!("( {inner_text})");
+    let mut status = CustomBarStatus::new(text);
+    status.class = lowest.state().into();
+    status.tooltip = batteries
+        .iter()
+        .zip(estimates(&batteries))
+        .map(|(battery, estimate)| match estimate {
+            Some(estimate) => format!("{battery} ({})", estimate.describe()),
+            None => battery.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(status)
+}
 
-    DeviceStatus::Unavailable
+/// Record the batteries in the history and get their estimated remaining time.
+/// Errors are only logged, as the estimates are just nice to have.
+fn estimates(batteries: &[BatteryStatus]) -> Vec<Option<Estimate>> {
+    record_batteries(batteries).unwrap_or_else(|err| {
+        warn!("Failed to record battery history:\n{err:#?}");
+        vec![None; batteries.len()]
+    })
 }
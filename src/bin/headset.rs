@@ -0,0 +1,133 @@
+//! Small convenience script to control USB headsets via headsetcontrol.
+//!
+//! This is currently used by me via shortcuts.
+//! Needed binaries:
+//! - headsetcontrol (>= 3.0)
+use anyhow::{Result, anyhow, bail};
+use clap::{ArgAction, Parser, ValueEnum};
+use script_utils::{headsetcontrol::*, logging, notify::*};
+
+#[derive(Parser, Debug)]
+#[clap(
+    name = "headset",
+    about = "Control the settings of USB headsets",
+    author = "Arne Beer <contact@arne.beer>"
+)]
+struct CliArguments {
+    /// Verbose mode (-v, -vv, -vvv)
+    #[clap(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// The index of the headset to control, as shown by `list`.
+    /// Defaults to the first headset.
+    #[clap(short, long)]
+    pub device: Option<usize>,
+
+    /// The command to execute.
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Parser, Clone, Debug, PartialEq)]
+pub enum Command {
+    // List all headsets with their battery and capabilities
+    List,
+    // Set how loud you hear your own voice (0-128)
+    Sidetone {
+        level: u8,
+    },
+    // Turn the lights on or off
+    Lights {
+        #[clap(value_enum)]
+        state: Switch,
+    },
+    // Set the minutes after which the headset turns itself off (0-90, 0 is never)
+    InactiveTime {
+        minutes: u8,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Switch {
+    On,
+    Off,
+}
+
+fn main() -> Result<()> {
+    // Parse commandline options.
+    let args = CliArguments::parse();
+    logging::init_logger(args.verbose);
+
+    let headsets = get_headsets()?;
+    if headsets.is_empty() {
+        bail!("Found no headsets");
+    }
+    match args.command {
+        // Listing shows all headsets, so it doesn't care about the selected one.
+        Command::List => list_headsets(&headsets),
+        command => control_headset(&headsets, args.device, command)?,
+    }
+
+    Ok(())
+}
+
+/// Change a setting of the selected headset and notify the user about it.
+fn control_headset(headsets: &[Headset], device: Option<usize>, command: Command) -> Result<()> {
+    let index = device.unwrap_or_default();
+    let headset = headsets.get(index).ok_or(anyhow!(
+        "There's no headset with index {index}, valid indices are 0..{}",
+        headsets.len()
+    ))?;
+
+    let message = match command {
+        Command::List => return Ok(()),
+        Command::Sidetone { level } => {
+            ensure_support(headset, Capability::Sidetone)?;
+            set_sidetone(device, level)?;
+            format!("Sidetone: {level}")
+        }
+        Command::Lights { state } => {
+            ensure_support(headset, Capability::Lights)?;
+            set_lights(device, state == Switch::On)?;
+            format!("Lights: {state:?}")
+        }
+        Command::InactiveTime { minutes } => {
+            ensure_support(headset, Capability::InactiveTime)?;
+            set_inactive_time(device, minutes)?;
+            format!("Turning off after {minutes} minutes")
+        }
+    };
+    notify(1500, format!("{}: {message}", headset.device))?;
+
+    Ok(())
+}
+
+fn ensure_support(headset: &Headset, capability: Capability) -> Result<()> {
+    if !headset.supports(capability) {
+        bail!("{} doesn't support {capability:?}", headset.device);
+    }
+
+    Ok(())
+}
+
+/// Print all headsets and what they're capable of.
+fn list_headsets(headsets: &[Headset]) {
+    for (index, headset) in headsets.iter().enumerate() {
+        let battery = match &headset.battery {
+            Some(battery) => match battery.percentage() {
+                Some(percentage) if battery.is_charging() => format!("{percentage}% (charging)"),
+                Some(percentage) if battery.is_available() => format!("{percentage}%"),
+                _ => "unavailable".to_string(),
+            },
+            None => "unknown".to_string(),
+        };
+
+        println!(
+            "{index}: {}\n \
+            Battery: {battery}\n \
+            Capabilities: {}\n",
+            headset.device,
+            headset.capabilities.join(", "),
+        );
+    }
+}
@@ -4,17 +4,44 @@
 //! - IP Address
 //! - Type
 //! - Signal strength
-use anyhow::Result;
+//! - Throughput (optional)
+//!
+//! The tooltip contains the SSID, frequency band and bitrate of wireless connections, as well as
+//! the current throughput and the traffic of this session.
+//! The throughput is calculated from the last sample, which is kept in the runtime dir.
+//! If a link is close to its speed, it's marked as `warning` or `critical`.
+//!
+//! The interface that carries the default route is always listed first.
+//!
+//! Interfaces are classified via `/sys/class/net`. Which of them are shown can be configured
+//! in `~/.config/netinfo.toml`, see [NetinfoConfig].
+//!
+//! Interfaces, addresses and routes are read via netlink. In watch mode, the status is refreshed
+//! as soon as the kernel announces a change, e.g. when connecting to a network.
+//!
+//! Needed binaries:
+//! - ip (only if netlink isn't available)
+//! - iw
+use std::{
+    collections::HashMap,
+    fs::{read_to_string, write},
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+    thread::spawn,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, anyhow};
+use chrono::Utc;
 use clap::{ArgAction, Parser};
-use log::{debug, warn};
-use regex::Regex;
-use script_utils::{exec::Cmd, ip_addr::*, logging};
-
-enum NetworkType {
-    Ethernet,
-    Wlan,
-    Vpn,
-}
+use dirs::runtime_dir;
+use log::{debug, error, warn};
+use script_utils::{
+    i3status::{BarFormat, CustomBarStatus, MouseButton, StatusLoop, StatusPrinter},
+    ip_addr::*,
+    logging,
+    network::*,
+};
 
 #[derive(Parser, Debug)]
 #[clap(
@@ -26,6 +53,27 @@ struct CliArguments {
     /// Verbose mode (-v, -vv, -vvv)
     #[clap(short, long, action = ArgAction::Count)]
     pub verbose: u8,
+
+    /// The output format.
+    #[clap(short, long, value_enum, default_value_t = BarFormat::Plain)]
+    pub format: BarFormat,
+
+    /// Keep running and print a new line whenever the network state changes.
+    #[clap(short, long)]
+    pub watch: bool,
+
+    /// The refresh interval (in seconds) in watch mode.
+    #[clap(short, long, default_value = "5")]
+    pub interval: u64,
+
+    /// Handle a click on the module and exit.
+    /// Meant to be used in waybar's `on-scroll-*` options.
+    #[clap(short, long, value_enum)]
+    pub click: Option<MouseButton>,
+
+    /// Show the current download and upload rates in the bar text.
+    #[clap(short, long)]
+    pub throughput: bool,
 }
 
 /// Print a string, representing the current network state with IP.
@@ -33,125 +81,283 @@ fn main() -> Result<()> {
     // Parse commandline options.
     let args = CliArguments::parse();
     logging::init_logger(args.verbose);
+    let mut printer = StatusPrinter::new(args.format);
 
-    let interfaces = get_interfaces()?;
+    if let Some(button) = args.click {
+        return handle_click(button);
+    }
 
-    let mut output = Vec::new();
+    if args.watch {
+        let interval = Duration::from_secs(args.interval);
+        let status_loop = StatusLoop::new(printer, interval);
 
-    for interface in interfaces {
-        // We aren't interested in the loopback device
-        if interface.ifname == "lo" {
-            continue;
+        let sender = status_loop.event_sender();
+        spawn(move || watch_network_changes(sender));
+
+        return status_loop
+            .on_click(|event| match event.mouse_button() {
+                Some(button) => handle_click(button),
+                None => Ok(()),
+            })
+            .run(|| network_status(args.throughput));
+    }
+
+    printer.print(&network_status(args.throughput)?)?;
+
+    Ok(())
+}
+
+/// Trigger a refresh whenever links, addresses or routes change.
+/// If we cannot subscribe to changes, the status is only refreshed in the normal interval.
+fn watch_network_changes(sender: Sender<()>) {
+    let monitor = match NetlinkMonitor::new() {
+        Ok(monitor) => monitor,
+        Err(err) => {
+            warn!("Couldn't subscribe to network changes: {err:?}");
+            return;
         }
+    };
 
-        // Device doesn't have an active connection.
-        if interface.addr_info.is_empty() || interface.operstate == "DOWN" {
-            continue;
+    loop {
+        match monitor.receive(Duration::from_secs(60)) {
+            Ok(events) if events.is_empty() => continue,
+            Ok(events) => debug!("Network changed: {events:?}"),
+            Err(err) => {
+                error!("Failed to receive network changes: {err:?}");
+                return;
+            }
         }
 
-        debug!("Interface info: {interface:#?}");
+        if sender.send(()).is_err() {
+            return;
+        }
+    }
+}
+
+/// Build a status, representing the current network state with IP.
+///
+/// If the user scrolled to a specific interface, only that interface is shown.
+fn network_status(show_throughput: bool) -> Result<CustomBarStatus> {
+    let entries = network_entries(show_throughput)?;
+
+    let selected = match read_selection().checked_sub(1) {
+        Some(index) if index < entries.len() => &entries[index..=index],
+        _ => &entries[..],
+    };
 
-        // Try to find an ipv4 address by default.
-        let addr = interface
-            .addr_info
+    let text = if selected.is_empty() {
+        "No network".to_string()
+    } else {
+        selected
             .iter()
-            .find(|addr| addr.family == "inet");
-
-        // Search for an ipv6 address as fallback.
-        let addr = if let Some(addr) = addr {
-            addr
-        } else {
-            let ipv6_addr = interface
-                .addr_info
-                .iter()
-                .find(|addr| addr.family == "inet6");
-
-            match ipv6_addr {
-                Some(addr) => addr,
-                None => continue,
-            }
-        };
+            .map(|entry| entry.text.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
 
-        let name = interface.ifname;
-        let ip_addr = &addr.local;
+    let mut status = CustomBarStatus::new(text);
+    // Show the most saturated of the shown links.
+    if selected.iter().any(|entry| entry.class == "critical") {
+        status.class = "critical".into();
+    } else if selected.iter().any(|entry| entry.class == "warning") {
+        status.class = "warning".into();
+    }
+    status.tooltip = entries
+        .iter()
+        .map(|entry| entry.tooltip.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(status)
+}
+
+/// Scroll through the interfaces.
+///
+/// The selection `0` shows all interfaces, every other selection shows a single interface.
+fn handle_click(button: MouseButton) -> Result<()> {
+    // Account for the "all interfaces" entry.
+    let count = shown_interfaces(&NetinfoConfig::load()?)?.len() + 1;
+    let selection = read_selection().min(count - 1);
+
+    let selection = match button {
+        MouseButton::ScrollUp => (selection + count - 1) % count,
+        MouseButton::ScrollDown => (selection + 1) % count,
+        _ => return Ok(()),
+    };
+    debug!("Selecting interface entry {selection}");
+
+    write(selection_file_path()?, selection.to_string()).context("Failed to write selection")?;
+
+    Ok(())
+}
+
+fn selection_file_path() -> Result<PathBuf> {
+    Ok(runtime_dir()
+        .ok_or(anyhow!("Couldn't find runtime dir"))?
+        .join("netinfo-selection"))
+}
+
+/// Read the currently selected entry. Defaults to all interfaces.
+fn read_selection() -> usize {
+    selection_file_path()
+        .ok()
+        .and_then(|path| read_to_string(path).ok())
+        .and_then(|selection| selection.trim().parse().ok())
+        .unwrap_or_default()
+}
 
-        // Drop any container/virtual environment related networks
-        if name.starts_with("docker") || name.starts_with("veth") || name.starts_with("br") {
+/// The bar text, tooltip and class of a single interface.
+struct NetworkEntry {
+    text: String,
+    tooltip: String,
+    class: &'static str,
+    /// Whether this interface carries the default route.
+    is_default: bool,
+}
+
+/// Get all active interfaces that the user wants to see, together with their kind.
+///
+/// This doesn't sample any traffic counters, so it's safe to call outside the status loop.
+fn shown_interfaces(config: &NetinfoConfig) -> Result<Vec<(Interface, InterfaceKind)>> {
+    let sys_class_net = Path::new(SYS_CLASS_NET);
+    let mut shown = Vec::new();
+    for interface in get_interfaces()? {
+        // Device doesn't have an active connection.
+        if !interface.link.is_up() || interface.preferred_address().is_none() {
             continue;
         }
 
-        // Determine the network type based on the name of the interface.
-        let network_type = if name.starts_with('e') {
-            NetworkType::Ethernet
-        } else if name.starts_with('w') {
-            NetworkType::Wlan
-        } else {
-            NetworkType::Vpn
-        };
-
-        // Set the symbol for the current network type.
-        let symbol = match network_type {
-            NetworkType::Ethernet => "".into(),
-            NetworkType::Wlan => format!(" {}", wifi_strength(&name)),
-            NetworkType::Vpn => "".into(),
-        };
+        debug!("Interface info: {interface:#?}");
 
-        output.push(format!("{symbol} {name}: {ip_addr}"));
-    }
+        // Drop loopback, container and other interfaces the user isn't interested in.
+        let name = &interface.link.ifname;
+        let kind = classify_interface(sys_class_net, name);
+        if !config.is_shown(name, kind) {
+            debug!("Skipping {kind} interface {name}");
+            continue;
+        }
 
-    if output.is_empty() {
-        println!("No network");
-    } else {
-        println!("{}", output.join(", "));
+        shown.push((interface, kind));
     }
 
-    Ok(())
+    Ok(shown)
 }
 
-/// Determine the network strength of a given device.
-/// -30 dBm Maximum signal strength, you are probably standing right next to the access point /
-/// router. -50 dBm Anything down to this level can be regarded as excellent signal strength.
-/// -60 dBm This is still good, reliable signal strength.
-/// -67 dBm This is the minimum value for all services that require smooth and reliable data
-/// traffic.  VoIP/VoWi-Fi Video streaming/streaming (not the highest quality)
-/// -70 dBm The signal is not very strong, but mostly sufficient. Web, email, and the like
-/// -80 dBm Minimum value required to make a connection.
-///  You cannot count on a reliable connection or sufficient signal strength to use services at this
-/// level. -90 dBm It is very unlikely that you will be able to connect or make use of any services
-/// with this signal strength.
-pub fn wifi_strength(interface: &str) -> &'static str {
-    let capture_data =
-        Cmd::new(format!("iw dev {interface} info | rg '^.*txpower.*'")).run_success();
-    // Return an wifi error symbol if the signal strength cannot be determined.
-    let capture_data = match capture_data {
-        Ok(capture) => capture,
-        Err(err) => {
-            warn!("Got error reading interface info: {err:#?}");
-            return "";
+/// Get a formatted entry for each active network interface.
+fn network_entries(show_throughput: bool) -> Result<Vec<NetworkEntry>> {
+    let config = NetinfoConfig::load()?;
+    let signals = read_wireless_signals();
+    let sys_class_net = Path::new(SYS_CLASS_NET);
+    let mut traffic = TrafficState::load()?;
+    let now = Utc::now().timestamp_millis();
+    let mut names = Vec::new();
+    let routes = get_routes().unwrap_or_else(|err| {
+        debug!("Couldn't get routes: {err:?}");
+        Vec::new()
+    });
+    let default = default_route(&routes);
+
+    let mut output = Vec::new();
+
+    for (interface, kind) in shown_interfaces(&config)? {
+        let Some(addr) = interface.preferred_address() else {
+            continue;
+        };
+
+        let name = interface.link.ifname.clone();
+        let ip_addr = &addr.local;
+
+        // Set the symbol for the current network type.
+        let mut tooltip = format!("{name}: {ip_addr}/{}", addr.prefixlen);
+        let is_default = default.is_some_and(|route| route.dev.as_ref() == Some(&name));
+        if is_default && let Some(gateway) = default.and_then(|route| route.gateway.as_ref()) {
+            tooltip.push_str(&format!("\n  Default route via {gateway}"));
         }
-    };
+        let mut link_speed = link_speed_mbit(sys_class_net, &name);
+        let symbol = match kind {
+            InterfaceKind::Wireless => {
+                let link = wireless_link(&name);
+                link_speed = link_speed.or(link.as_ref().and_then(WirelessLink::speed_mbit));
+                let (bars, details) = wifi_info(&name, link.as_ref(), &signals);
+                if !details.is_empty() {
+                    tooltip.push_str(&format!("\n  {details}"));
+                }
+                format!(" {bars}")
+            }
+            kind if kind.is_tunnel() => "".into(),
+            _ => "".into(),
+        };
 
-    let re = Regex::new(r"txpower (\d*)\.\d* dBm").unwrap();
+        let mut text = format!("{symbol} {name}: {ip_addr}");
+        let mut class = "";
+        match read_counters(sys_class_net, &name) {
+            Ok(counters) => {
+                let throughput = traffic.update(&name, counters, now);
+                if let Some(rates) = throughput.rates() {
+                    tooltip.push_str(&format!("\n  {rates}"));
+                    if show_throughput {
+                        text.push_str(&format!(" {rates}"));
+                    }
+                }
+                tooltip.push_str(&format!("\n  Session: {}", throughput.totals()));
 
-    let output = String::from_utf8_lossy(&capture_data.stdout);
+                if let Some(utilization) =
+                    link_speed.and_then(|speed| throughput.utilization(speed))
+                {
+                    debug!("{name} uses {:.0}% of its link", utilization * 100.0);
+                    class = config.saturation_class(utilization);
+                }
+            }
+            Err(err) => debug!("Couldn't read traffic counters of {name}: {err:?}"),
+        }
 
-    debug!("Iw output: {output:#?}");
-    let captures = match re.captures(output.trim()) {
-        Some(captures) => captures,
-        None => return "",
-    };
+        names.push(name);
+        output.push(NetworkEntry {
+            text,
+            tooltip,
+            class,
+            is_default,
+        });
+    }
+    output.sort_by_key(|entry| !entry.is_default);
 
-    let level: usize = match captures.get(1).unwrap().as_str().parse() {
-        Ok(level) => level,
-        Err(_) => return "",
-    };
+    // Forget interfaces that're gone, so their session starts anew once they're back.
+    traffic.retain(&names);
+    traffic.save()?;
+
+    Ok(output)
+}
+
+/// Get the signal bars of a wireless interface and a description of its connection.
+fn wifi_info(
+    interface: &str,
+    link: Option<&WirelessLink>,
+    signals: &HashMap<String, WirelessSignal>,
+) -> (String, String) {
+    let level = signals
+        .get(interface)
+        .map(|signal| signal.level_dbm)
+        .or(link.and_then(|link| link.signal_dbm));
 
-    match level {
-        1..=30 => "▇",
-        51..=67 => "▅",
-        68..=70 => "▃",
-        71..=80 => "▁",
-        81..=90 => "!",
-        _ => "!",
+    // Return an wifi error symbol if the signal strength cannot be determined.
+    let bars = level.map(signal_bars).unwrap_or("");
+
+    let mut details = Vec::new();
+    if let Some(link) = link {
+        let ssid = link.ssid.as_deref().unwrap_or("unknown network");
+        match link.band() {
+            Some(band) => details.push(format!("{ssid} ({band})")),
+            None => details.push(ssid.to_string()),
+        }
+    }
+    if let Some(level) = level {
+        details.push(format!("{level} dBm"));
     }
+    if let Some(link) = link
+        && let (Some(rx), Some(tx)) = (&link.rx_bitrate, &link.tx_bitrate)
+    {
+        details.push(format!("↓{rx} ↑{tx}"));
+    }
+
+    (bars.to_string(), details.join(", "))
 }
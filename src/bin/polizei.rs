@@ -1,7 +1,8 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     fs::{File, remove_file},
     path::PathBuf,
+    time::{Duration, Instant},
 };
 
 use anyhow::{Result, anyhow};
@@ -11,8 +12,7 @@ use log::{debug, info};
 use script_utils::{
     logging,
     notify::*,
-    process::get_process_cmdlines,
-    sleep_seconds,
+    process::{ProcessEvent, ProcessWatcher, ResourceSampler, ResourceUsage},
     timer::{Phase, PhaseTimer},
 };
 
@@ -47,6 +47,12 @@ pub enum SubCommand {
         /// The interval at which the user will be notified to stop playing.
         #[clap(short, long, default_value = "10")]
         stop_notification_interval: i64,
+
+        /// The CPU usage (in percent of a single core) below which a game is considered idle.
+        /// Time in which games are idle, e.g. minimized, doesn't count towards the play time.
+        /// Set to `0` to disable idle detection.
+        #[clap(short, long, default_value = "5")]
+        cpu_threshold: f64,
     },
 
     /// Signal that you've acknowledged the gaming notification
@@ -121,6 +127,12 @@ impl RunningGame {
     fn check(&mut self) -> Option<GameAction> {
         self.timer.check_with_sleep_detection()
     }
+
+    /// Don't count the given idle time towards the play time.
+    fn pause(&mut self, duration: Duration) {
+        self.timer
+            .pause(chrono::Duration::from_std(duration).unwrap_or_default());
+    }
 }
 
 fn ack_file_path() -> Result<PathBuf> {
@@ -139,7 +151,13 @@ fn main() -> Result<()> {
             notification_interval,
             threshold,
             stop_notification_interval,
-        } => start(notification_interval, threshold, stop_notification_interval),
+            cpu_threshold,
+        } => start(
+            notification_interval,
+            threshold,
+            stop_notification_interval,
+            cpu_threshold,
+        ),
         SubCommand::Ack {} => {
             // Touch an ack file to indicate that the user has acknowledged the gaming notification.
             File::create(ack_file_path()?)?;
@@ -152,8 +170,10 @@ fn start(
     notification_interval: i64,
     threshold: i64,
     stop_notification_interval: i64,
+    cpu_threshold: f64,
 ) -> Result<()> {
     let mut running_games: HashMap<&'static str, RunningGame> = HashMap::new();
+    let mut sampler = ResourceSampler::new();
     let current_user_id = users::get_current_uid();
     info!(
         "\n
@@ -163,11 +183,80 @@ fn start(
         notification_interval, threshold, stop_notification_interval,
     );
 
-    // Check every few minutes whether any games are up and running.
-    // If they're running for the specified times, notify the user of this.
-    // Get more annoying if they're running past the threshold.
+    // All running processes that belong to a game, mapped to the game's name and strictness.
+    let mut game_pids: HashMap<i32, (&'static str, bool)> = HashMap::new();
+    let mut watcher = ProcessWatcher::new(current_user_id, Duration::from_secs(5));
+    let mut next_tick = Instant::now();
+    let mut last_tick: Option<Instant> = None;
+
+    // Get notified whenever a game is started or closed.
+    // Every minute, check whether they're running for the specified times and notify the user of
+    // this. Get more annoying if they're running past the threshold.
     loop {
-        let processes = get_process_cmdlines(current_user_id)?;
+        let timeout = next_tick.saturating_duration_since(Instant::now());
+        let mut events = watcher.wait(timeout)?;
+        let is_tick = Instant::now() >= next_tick;
+        if is_tick {
+            // Processes may rewrite their argv after they've been started, so look at those that
+            // don't belong to a game again.
+            events
+                .extend(watcher.refresh_cmdlines(|process| !game_pids.contains_key(&process.pid)));
+        }
+
+        for event in events {
+            let (process, game) = match event {
+                ProcessEvent::Started(process) => {
+                    debug!("Process started: {}", process.cmdline);
+                    let game = find_game(&process.cmdline);
+                    (process, game)
+                }
+                ProcessEvent::Changed(process) => {
+                    debug!("Process changed: {}", process.cmdline);
+                    let game = find_game(&process.cmdline);
+                    (process, game)
+                }
+                ProcessEvent::Exited(process) => (process, None),
+            };
+
+            match game {
+                Some((name, strict)) => {
+                    // Processes that `exec` the same game again just continue the session.
+                    if let Some((previous, _)) = game_pids.insert(process.pid, (name, strict))
+                        && previous != name
+                    {
+                        end_session_if_closed(previous, &game_pids, &mut running_games);
+                    }
+                    // Start the session right away, so it's tracked precisely.
+                    if !running_games.contains_key(name) {
+                        info!("{name} has been started.");
+                        running_games.insert(
+                            name,
+                            RunningGame::new(
+                                notification_interval,
+                                threshold,
+                                stop_notification_interval,
+                                strict,
+                            ),
+                        );
+                    }
+                }
+                // The process exited or doesn't belong to a game anymore.
+                None => {
+                    if let Some((name, _)) = game_pids.remove(&process.pid) {
+                        end_session_if_closed(name, &game_pids, &mut running_games);
+                    }
+                }
+            }
+        }
+
+        if !is_tick {
+            continue;
+        }
+        next_tick = Instant::now() + Duration::from_secs(60);
+        let since_last_tick = last_tick
+            .replace(Instant::now())
+            .map(|last_tick| last_tick.elapsed())
+            .unwrap_or_default();
 
         // Search for the ack file, if it exists, the user has acknowledged the notification.
         // Reset all timers and remove the file.
@@ -179,43 +268,86 @@ fn start(
             info!("Timers reset - user acknowledged gaming notification");
         }
 
-        let mut found_games: HashSet<&'static str> = HashSet::new();
-        // Check all processes for the specified binaries.
-        for cmdline in processes {
-            debug!("Looking at process: {cmdline}");
-            for (name, binary, strict) in GAME_LIST {
-                // The cmdline doesn't contain the game just exit early.
-                if !cmdline.to_lowercase().contains(binary) {
-                    continue;
-                }
+        // Sample the resource usage of all game processes since the last tick.
+        let pids: Vec<i32> = game_pids.keys().copied().collect();
+        let usages = sampler.sample(&pids);
 
-                info!("Found running game {name}");
-                found_games.insert(name);
-                handle_running_game(
-                    notification_interval,
-                    threshold,
-                    stop_notification_interval,
-                    &mut running_games,
-                    name,
-                    *strict,
-                )?;
-                break;
-            }
+        // Group the processes by their game.
+        let mut game_processes: HashMap<&'static str, (bool, Vec<i32>)> = HashMap::new();
+        for (pid, (name, strict)) in &game_pids {
+            let (_, pids) = game_processes.entry(name).or_insert((*strict, Vec::new()));
+            pids.push(*pid);
         }
 
-        // Remove games that're no longer active.
-        for key in running_games.keys().copied().collect::<Vec<&'static str>>() {
-            if !found_games.contains(key) {
-                info!("{key} has been closed.");
-                // Remove the game from the list of running games.
-                running_games.remove(key);
+        for (name, (strict, pids)) in game_processes {
+            // Idle games keep their session, but the idle time doesn't count towards the play
+            // time and they don't get any notifications.
+            // Sessions only end once the game's processes exited.
+            // If a game stays idle for long, the timer's sleep detection resets the session.
+            if is_idle(&pids, &usages, cpu_threshold) {
+                info!("{name} is idle.");
+                if let Some(game) = running_games.get_mut(name) {
+                    game.pause(since_last_tick);
+                }
+                continue;
             }
+
+            info!("Found running game {name}");
+            handle_running_game(
+                notification_interval,
+                threshold,
+                stop_notification_interval,
+                &mut running_games,
+                name,
+                strict,
+            )?;
         }
+    }
+}
 
-        sleep_seconds(60);
+/// End the session of a game once the last of its processes is gone.
+fn end_session_if_closed(
+    name: &'static str,
+    game_pids: &HashMap<i32, (&'static str, bool)>,
+    running_games: &mut HashMap<&'static str, RunningGame>,
+) {
+    if !game_pids.values().any(|(game, _)| *game == name) {
+        info!("{name} has been closed.");
+        running_games.remove(name);
     }
 }
 
+/// Check whether a cmdline belongs to one of the watched games.
+/// Returns the name of the game and whether it's strict.
+fn find_game(cmdline: &str) -> Option<(&'static str, bool)> {
+    let cmdline = cmdline.to_lowercase();
+    GAME_LIST
+        .iter()
+        .find(|(_, binary, _)| cmdline.contains(binary))
+        .map(|(name, _, strict)| (*name, *strict))
+}
+
+/// Check whether the processes of a game used less CPU than the threshold since the last tick.
+///
+/// Games for which we don't have any samples yet are considered to be active.
+fn is_idle(pids: &[i32], usages: &HashMap<i32, ResourceUsage>, cpu_threshold: f64) -> bool {
+    if cpu_threshold <= 0.0 {
+        return false;
+    }
+
+    let mut sampled = false;
+    let mut cpu_percent = 0.0;
+    for pid in pids {
+        if let Some(usage) = usages.get(pid) {
+            sampled = true;
+            cpu_percent += usage.cpu_percent;
+        }
+    }
+    debug!("CPU usage of pids {pids:?}: {cpu_percent:.1}%");
+
+    sampled && cpu_percent < cpu_threshold
+}
+
 fn handle_running_game(
     notification_interval: i64,
     threshold: i64,
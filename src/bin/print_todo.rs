@@ -1,16 +1,47 @@
 //! This script prints a minimal summary of my todo list.
 //! It's designed for use in a status bar.
-use std::{fs::read_to_string, path::PathBuf};
+use std::{
+    fs::{metadata, read_to_string},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::mpsc::Sender,
+    thread::{sleep, spawn},
+    time::{Duration, SystemTime},
+};
 
 use anyhow::Result;
 use clap::Parser;
-use script_utils::{Context, i3status::CustomBarStatus};
+use script_utils::{
+    Context,
+    i3status::{
+        BarFormat,
+        BarOutput,
+        CustomBarStatus,
+        I3BarBlock,
+        MouseButton,
+        StatusLoop,
+        StatusPrinter,
+    },
+};
 use serde::Serialize;
 
 #[derive(Parser, Debug)]
 pub struct CliArguments {
     /// The path to the todo markdown file.
     pub path: PathBuf,
+
+    /// The output format.
+    #[clap(short, long, value_enum, default_value_t = BarFormat::Waybar)]
+    pub format: BarFormat,
+
+    /// Keep running and print a new line whenever the todo file changes.
+    #[clap(short, long)]
+    pub watch: bool,
+
+    /// Handle a click on the module and exit.
+    /// Meant to be used in waybar's `on-click` options.
+    #[clap(short, long, value_enum)]
+    pub click: Option<MouseButton>,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -40,13 +71,45 @@ impl Item {
     }
 }
 
-pub fn todos_as_i3bar_output(_todos: Vec<Todo>) -> CustomBarStatus {
-    let text = String::new();
+/// All todos of a todo file.
+/// This is `None` if there's no todo file.
+pub struct Todos(pub Option<Vec<Todo>>);
+
+impl BarOutput for Todos {
+    fn waybar(&self) -> CustomBarStatus {
+        match &self.0 {
+            Some(todos) => todos_as_waybar_output(todos),
+            None => CustomBarStatus::new(NOTHING_TO_DO.into()),
+        }
+    }
+
+    fn i3bar(&self) -> Vec<I3BarBlock> {
+        match &self.0 {
+            Some(todos) => vec![todos_as_i3bar_output(todos)],
+            None => vec![I3BarBlock::new(NOTHING_TO_DO.into())],
+        }
+    }
+}
+
+const NOTHING_TO_DO: &str = "Nothing to do :)";
+
+/// i3bar doesn't support tooltips, so list the names of all todos in the text instead.
+/// The short text only contains the count, in case there's not enough space.
+pub fn todos_as_i3bar_output(todos: &[Todo]) -> I3BarBlock {
+    let todo_count = todos.len();
+    if todo_count == 0 {
+        return I3BarBlock::new("Neat :3".into());
+    }
+
+    let names: Vec<&str> = todos.iter().map(|todo| todo.name.as_str()).collect();
+    let mut block = I3BarBlock::new(format!("{todo_count} todos: {}", names.join(", ")));
+    block.short_text = Some(format!("{todo_count} todos"));
+    block.name = Some("todo".into());
 
-    CustomBarStatus::new(text)
+    block
 }
 
-pub fn todos_as_waybar_output(todos: Vec<Todo>) -> CustomBarStatus {
+pub fn todos_as_waybar_output(todos: &[Todo]) -> CustomBarStatus {
     let mut text = String::new();
     let mut tooltip = String::new();
 
@@ -60,7 +123,7 @@ pub fn todos_as_waybar_output(todos: Vec<Todo>) -> CustomBarStatus {
     for todo in todos {
         tooltip.push_str(" ");
         tooltip.push_str(&todo.name);
-        for item in todo.items {
+        for item in &todo.items {
             tooltip.push('\r');
             if item.completed {
                 tooltip.push('');
@@ -73,9 +136,6 @@ pub fn todos_as_waybar_output(todos: Vec<Todo>) -> CustomBarStatus {
         tooltip.push('\r');
     }
 
-    println!("{text}");
-    println!("{tooltip}");
-
     let mut status = CustomBarStatus::new(text);
     status.tooltip = tooltip;
 
@@ -87,22 +147,79 @@ fn main() -> Result<()> {
     // Parse commandline options.
     let args = CliArguments::parse();
 
-    if !args.path.exists() {
-        println!("Nothing to do :)");
-        return Ok(());
+    let mut printer = StatusPrinter::new(args.format);
+
+    if let Some(button) = args.click {
+        return handle_click(&args.path, button);
     }
 
-    let content = read_to_string(args.path).context("Failed to read file")?;
-    let todos = handle_todo_items(content);
+    if args.watch {
+        let status_loop = StatusLoop::new(printer, Duration::from_secs(60));
 
-    let output = todos_as_waybar_output(todos);
+        let sender = status_loop.event_sender();
+        let path = args.path.clone();
+        spawn(move || watch_file(&path, sender));
 
-    // Send the expected json output to i3status
-    println!("{}", serde_json::to_string(&output)?);
+        let path = args.path.clone();
+        return status_loop
+            .on_click(move |event| match event.mouse_button() {
+                Some(button) => handle_click(&path, button),
+                None => Ok(()),
+            })
+            .run(|| read_todos(&args.path));
+    }
+
+    // Send the expected output to the status bar.
+    printer.print(&read_todos(&args.path)?)?;
 
     Ok(())
 }
 
+/// Read and parse the todo file.
+fn read_todos(path: &Path) -> Result<Todos> {
+    if !path.exists() {
+        return Ok(Todos(None));
+    }
+
+    let content = read_to_string(path).context("Failed to read file")?;
+    Ok(Todos(Some(handle_todo_items(content))))
+}
+
+/// Open the todo file on any click.
+fn handle_click(path: &Path, button: MouseButton) -> Result<()> {
+    if matches!(button, MouseButton::ScrollUp | MouseButton::ScrollDown) {
+        return Ok(());
+    }
+
+    // Don't block until the editor is closed, but still reap the process once it is.
+    let mut child = Command::new("xdg-open")
+        .arg(path)
+        .spawn()
+        .context("Failed to open todo file")?;
+    spawn(move || child.wait());
+
+    Ok(())
+}
+
+/// Check the modification time of the todo file every second and send an event if it changed.
+/// Stops once the receiving side is gone.
+fn watch_file(path: &Path, sender: Sender<()>) {
+    let modified = |path: &Path| -> Option<SystemTime> { metadata(path).ok()?.modified().ok() };
+
+    let mut last_modified = modified(path);
+    loop {
+        sleep(Duration::from_secs(1));
+
+        let current = modified(path);
+        if current != last_modified {
+            last_modified = current;
+            if sender.send(()).is_err() {
+                return;
+            }
+        }
+    }
+}
+
 /// Go through all lines of a todo text and extract information from it.
 /// For example, the amount items that were completed.
 ///
@@ -0,0 +1,103 @@
+//! Small convenience script to control the volume of audio devices.
+//! By default, the current default sink is targeted.
+//!
+//! This is currently used by me via shortcuts.
+//! Needed binaries:
+//! - pw-dump
+//! - wpctl
+use anyhow::{Result, anyhow};
+use clap::{ArgAction, Parser};
+use script_utils::{logging, pipewire::*};
+
+#[derive(Parser, Debug)]
+#[clap(
+    name = "volume",
+    about = "Get and change the volume of audio devices",
+    author = "Arne Beer <contact@arne.beer>"
+)]
+struct CliArguments {
+    /// Verbose mode (-v, -vv, -vvv)
+    #[clap(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Target the default source (microphone) instead of the default sink.
+    #[clap(short, long)]
+    pub source: bool,
+
+    /// Target a specific node by its id instead of the default one.
+    #[clap(short, long)]
+    pub node: Option<usize>,
+
+    /// The maximum volume in percent.
+    #[clap(short, long, default_value = "100")]
+    pub max: f64,
+
+    /// The command to execute.
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Parser, Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Print the current volume.
+    Get,
+    /// Set the volume to the given percentage.
+    Set { percent: f64 },
+    /// Increase the volume by the given percentage.
+    Up {
+        #[clap(default_value = "5")]
+        step: f64,
+    },
+    /// Decrease the volume by the given percentage.
+    Down {
+        #[clap(default_value = "5")]
+        step: f64,
+    },
+    /// Toggle mute.
+    Mute,
+}
+
+fn main() -> Result<()> {
+    // Parse commandline options.
+    let args = CliArguments::parse();
+    logging::init_logger(args.verbose);
+
+    let kind = if args.source {
+        NodeKind::Source
+    } else {
+        NodeKind::Sink
+    };
+    let node = match args.node {
+        Some(id) => get_node_by_id(kind, id)?,
+        None => get_default_node(kind)?,
+    };
+    let current = get_volume(&node).ok_or(anyhow!(
+        "Couldn't determine the volume of {}",
+        node.info.props.node_description
+    ))?;
+
+    let volume = match args.command {
+        Command::Get => {
+            if current.muted {
+                println!("muted ({}%)", current.percent.round());
+            } else {
+                println!("{}%", current.percent.round());
+            }
+            return Ok(());
+        }
+        Command::Set { percent } => Volume {
+            percent: set_volume(&node, percent, args.max)?,
+            ..current
+        },
+        Command::Up { step } => step_volume(&node, step, args.max)?,
+        Command::Down { step } => step_volume(&node, -step, args.max)?,
+        Command::Mute => Volume {
+            muted: toggle_mute(&node)?,
+            ..current
+        },
+    };
+
+    notify_volume(&node, volume)?;
+
+    Ok(())
+}
@@ -10,6 +10,8 @@ use subprocess::{Capture, Exec, Redirection};
 pub struct Cmd {
     cwd: Option<String>,
     env: HashMap<String, String>,
+    stdin: Option<String>,
+    separate_stderr: bool,
     command: String,
 }
 
@@ -19,6 +21,8 @@ impl Cmd {
         Cmd {
             command: command.to_string(),
             env: HashMap::new(),
+            stdin: None,
+            separate_stderr: false,
             cwd: None,
         }
     }
@@ -36,11 +40,38 @@ impl Cmd {
         self
     }
 
+    /// Pass the given input to the process' stdin.
+    pub fn stdin<T: ToString>(mut self, input: T) -> Cmd {
+        self.stdin = Some(input.to_string());
+        self
+    }
+
+    /// Append a single argument to the command.
+    /// The argument is quoted, so it reaches the process verbatim and is never interpreted by
+    /// the shell.
+    pub fn arg<T: AsRef<str>>(mut self, arg: T) -> Cmd {
+        self.command.push(' ');
+        self.command.push_str(&shell_quote(arg.as_ref()));
+        self
+    }
+
+    /// Capture stderr separately instead of merging it into stdout.
+    /// Use this if stdout is parsed, as warnings on stderr would get in the way.
+    pub fn separate_stderr(mut self) -> Cmd {
+        self.separate_stderr = true;
+        self
+    }
+
     /// Run the command and return the exit status
     pub fn run(&self) -> Result<Capture> {
+        let stderr = if self.separate_stderr {
+            Redirection::Pipe
+        } else {
+            Redirection::Merge
+        };
         let mut exec = Exec::shell(&self.command)
             .stdout(Redirection::Pipe)
-            .stderr(Redirection::Merge);
+            .stderr(stderr);
 
         // Set the current working directory.
         if let Some(cwd) = &self.cwd {
@@ -51,6 +82,10 @@ impl Cmd {
             exec = exec.env(key, value);
         }
 
+        if let Some(input) = &self.stdin {
+            exec = exec.stdin(input.clone().into_bytes());
+        }
+
         // Check if there are any critical errors.
         let capture = match exec.capture() {
             Ok(capture) => capture,
@@ -73,13 +108,33 @@ impl Cmd {
         // Return an error on any non-1 exit codes
         if !capture.exit_status.success() {
             bail!(
-                "Failed during: {}\nGot non-zero exit code: {:?}:\n{}",
+                "Failed during: {}\nGot non-zero exit code: {:?}:\n{}{}",
                 &self.command,
                 capture.exit_status,
                 capture.stdout_str(),
+                capture.stderr_str(),
             );
         }
 
         Ok(capture)
     }
 }
+
+/// Wrap a string in single quotes, so the shell passes it on as a single literal argument.
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arg_is_passed_verbatim() -> Result<()> {
+        let message = "Bob's AirPods $(echo injected) `true` \"quoted\"";
+        let capture = Cmd::new("printf %s").arg(message).run_success()?;
+        assert_eq!(capture.stdout_str(), message);
+
+        Ok(())
+    }
+}
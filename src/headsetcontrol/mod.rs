@@ -0,0 +1,208 @@
+//! Control USB headsets via [headsetcontrol](https://github.com/Sapd/HeadsetControl).
+//!
+//! We use the JSON output (`--output json`), which has been added in headsetcontrol 3.0.
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+use crate::exec::Cmd;
+
+/// The top-level output of `headsetcontrol --output json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HeadsetControlOutput {
+    pub version: String,
+    #[serde(default)]
+    pub devices: Vec<Headset>,
+}
+
+/// A single headset, as reported by headsetcontrol.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Headset {
+    /// Either `success` or `partial`, if some of the requested info couldn't be read.
+    pub status: String,
+    /// The full name of the device, e.g. `SteelSeries Arctis Nova 7`.
+    pub device: String,
+    pub vendor: Option<String>,
+    pub product: Option<String>,
+    /// Capabilities in machine-readable form, e.g. `CAP_SIDETONE`.
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    pub battery: Option<HeadsetBattery>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HeadsetBattery {
+    /// `BATTERY_AVAILABLE`, `BATTERY_CHARGING`, `BATTERY_UNAVAILABLE` or an error state.
+    pub status: String,
+    /// The battery level in percent. Negative if it's unknown.
+    pub level: i64,
+}
+
+impl HeadsetBattery {
+    pub fn is_charging(&self) -> bool {
+        self.status == "BATTERY_CHARGING"
+    }
+
+    /// Whether the headset is turned on and reported its battery.
+    pub fn is_available(&self) -> bool {
+        self.status == "BATTERY_AVAILABLE" || self.is_charging()
+    }
+
+    /// The battery level in percent, if it's known.
+    pub fn percentage(&self) -> Option<usize> {
+        usize::try_from(self.level).ok()
+    }
+}
+
+/// The capabilities we can control.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Capability {
+    Sidetone,
+    Battery,
+    Lights,
+    InactiveTime,
+}
+
+impl Capability {
+    /// The name of the capability in headsetcontrol's JSON output.
+    pub fn json_name(&self) -> &'static str {
+        match self {
+            Capability::Sidetone => "CAP_SIDETONE",
+            Capability::Battery => "CAP_BATTERY_STATUS",
+            Capability::Lights => "CAP_LIGHTS",
+            Capability::InactiveTime => "CAP_INACTIVE_TIME",
+        }
+    }
+}
+
+impl Headset {
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities
+            .iter()
+            .any(|name| name == capability.json_name())
+    }
+}
+
+/// Parse the output of `headsetcontrol --output json`.
+pub fn parse_headsetcontrol_json(output: &str) -> Result<HeadsetControlOutput> {
+    serde_json::from_str(output).context("Failed to parse headsetcontrol output")
+}
+
+/// Get all connected headsets and their battery status.
+pub fn get_headsets() -> Result<Vec<Headset>> {
+    read_headsets("headsetcontrol --battery --output json")
+}
+
+/// Run a command that prints headsetcontrol's JSON and parse it.
+///
+/// hidapi and headsetcontrol like to print warnings on stderr, which must not end up in the
+/// JSON, so only stdout is parsed.
+fn read_headsets(command: &str) -> Result<Vec<Headset>> {
+    let capture = Cmd::new(command).separate_stderr().run_success()?;
+
+    Ok(parse_headsetcontrol_json(&capture.stdout_str())?.devices)
+}
+
+/// Run headsetcontrol with the given argument on a specific device or the first one.
+fn control(device: Option<usize>, argument: String) -> Result<()> {
+    let mut command = String::from("headsetcontrol");
+    if let Some(device) = device {
+        command.push_str(&format!(" --device {device}"));
+    }
+    command.push_str(&format!(" {argument}"));
+    Cmd::new(command).run_success()?;
+
+    Ok(())
+}
+
+/// Set how loud you hear your own voice, from 0 to 128.
+pub fn set_sidetone(device: Option<usize>, level: u8) -> Result<()> {
+    if level > 128 {
+        bail!("The sidetone level must be between 0 and 128");
+    }
+
+    control(device, format!("--sidetone {level}"))
+}
+
+/// Turn the lights of the headset on or off.
+pub fn set_lights(device: Option<usize>, on: bool) -> Result<()> {
+    control(device, format!("--light {}", u8::from(on)))
+}
+
+/// Set the minutes after which the headset turns itself off, from 0 (never) to 90.
+pub fn set_inactive_time(device: Option<usize>, minutes: u8) -> Result<()> {
+    if minutes > 90 {
+        bail!("The inactive time must be between 0 and 90 minutes");
+    }
+
+    control(device, format!("--inactive-time {minutes}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_charging_headset() -> Result<()> {
+        let output = parse_headsetcontrol_json(include_str!("fixtures/charging.json"))?;
+        let headset = &output.devices[0];
+        let battery = headset.battery.as_ref().unwrap();
+
+        assert_eq!(headset.device, "SteelSeries Arctis Nova 7");
+        assert!(battery.is_charging());
+        assert_eq!(battery.percentage(), Some(85));
+        assert!(headset.supports(Capability::Sidetone));
+        assert!(!headset.supports(Capability::Lights));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parses_unavailable_headset() -> Result<()> {
+        let output = parse_headsetcontrol_json(include_str!("fixtures/unavailable.json"))?;
+        let battery = output.devices[0].battery.as_ref().unwrap();
+
+        assert!(!battery.is_available());
+        assert_eq!(battery.percentage(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parses_multiple_headsets() -> Result<()> {
+        let output = parse_headsetcontrol_json(include_str!("fixtures/multiple.json"))?;
+        let names: Vec<&str> = output
+            .devices
+            .iter()
+            .map(|headset| headset.device.as_str())
+            .collect();
+
+        assert_eq!(
+            names,
+            ["Logitech G PRO X Wireless", "Corsair Void Wireless"]
+        );
+        assert!(output.devices[1].supports(Capability::Lights));
+        assert!(output.devices[1].battery.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn ignores_stderr() -> Result<()> {
+        let fixture = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/headsetcontrol/fixtures/charging.json"
+        );
+        let headsets = read_headsets(&format!(
+            "echo 'hidapi: Failed to open device' >&2; cat '{fixture}'; echo 'Found no lights' >&2"
+        ))?;
+
+        assert_eq!(headsets[0].device, "SteelSeries Arctis Nova 7");
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_text_output() {
+        assert!(parse_headsetcontrol_json("Found SteelSeries Arctis Nova 7!").is_err());
+    }
+}
@@ -1,6 +1,22 @@
-use serde::Serialize;
+//! Output formats for status bars.
+//!
+//! - Waybar custom modules expect one JSON object with `text`, `tooltip` and `class` per line.
+//! - i3bar and swaybar speak the [i3bar protocol](https://i3wm.org/docs/i3bar-protocol.html), which
+//!   consists of a header followed by an infinite array of block lists.
+//! - Plain output just prints the text, which is understood by pretty much any bar.
+use std::{
+    io::{self, BufRead, Write, stdin, stdout},
+    sync::mpsc::{Receiver, Sender, channel},
+    thread::spawn,
+    time::Duration,
+};
 
-#[derive(Serialize, Default)]
+use anyhow::Result;
+use clap::ValueEnum;
+use log::{debug, error, warn};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Default, Clone, Debug, PartialEq)]
 pub struct CustomBarStatus {
     pub text: String,
     #[serde(skip_serializing_if = "String::is_empty")]
@@ -17,3 +33,404 @@ impl CustomBarStatus {
         }
     }
 }
+
+/// The header that's sent once at the start of the i3bar protocol.
+#[derive(Serialize, Debug)]
+pub struct I3BarHeader {
+    pub version: usize,
+    pub click_events: bool,
+}
+
+/// A single block of the i3bar protocol.
+#[derive(Serialize, Default, Clone, Debug, PartialEq)]
+pub struct I3BarBlock {
+    pub full_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short_text: Option<String>,
+    /// The text color in `#RRGGBB` notation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub urgent: bool,
+    /// Name and instance are sent back to us in click events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Either `pango` or `none`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markup: Option<String>,
+}
+
+impl I3BarBlock {
+    pub fn new(full_text: String) -> Self {
+        Self {
+            full_text,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<CustomBarStatus> for I3BarBlock {
+    /// Convert a waybar status into a block.
+    /// The tooltip is dropped, as i3bar doesn't have tooltips, and the class is mapped to the
+    /// colors i3status uses by default.
+    fn from(status: CustomBarStatus) -> Self {
+        let mut block = I3BarBlock::new(status.text);
+        match status.class.as_str() {
+            "good" => block.color = Some("#00FF00".into()),
+            "warning" => block.color = Some("#FFFF00".into()),
+            "critical" => {
+                block.color = Some("#FF0000".into());
+                block.urgent = true;
+            }
+            _ => (),
+        }
+
+        block
+    }
+}
+
+/// The format a status binary should print.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum BarFormat {
+    /// JSON for waybar's custom modules.
+    #[default]
+    Waybar,
+    /// The i3bar/swaybar JSON protocol.
+    I3bar,
+    /// Only the text.
+    Plain,
+}
+
+/// Anything that can be displayed in a status bar.
+pub trait BarOutput {
+    /// The status for waybar's custom modules.
+    fn waybar(&self) -> CustomBarStatus;
+
+    /// The blocks for i3bar/swaybar.
+    /// By default, this is a single block derived from the waybar status.
+    fn i3bar(&self) -> Vec<I3BarBlock> {
+        let status = self.waybar();
+        // Don't display empty blocks.
+        if status.text.is_empty() {
+            return Vec::new();
+        }
+
+        vec![status.into()]
+    }
+
+    /// The plain text.
+    fn plain(&self) -> String {
+        self.waybar().text
+    }
+}
+
+impl BarOutput for CustomBarStatus {
+    fn waybar(&self) -> CustomBarStatus {
+        self.clone()
+    }
+}
+
+/// Print statuses to stdout in the given format.
+///
+/// This takes care of the i3bar protocol's framing, so multiple statuses can be printed in a row.
+pub struct StatusPrinter {
+    format: BarFormat,
+    click_events: bool,
+    started: bool,
+}
+
+impl StatusPrinter {
+    pub fn new(format: BarFormat) -> Self {
+        Self {
+            format,
+            click_events: false,
+            started: false,
+        }
+    }
+
+    /// Announce to i3bar that we want to receive click events.
+    pub fn click_events(mut self, enabled: bool) -> Self {
+        self.click_events = enabled;
+
+        self
+    }
+
+    /// Render the status in the printer's format, without any protocol framing.
+    pub fn content(&self, status: &impl BarOutput) -> Result<String> {
+        let content = match self.format {
+            BarFormat::Waybar => serde_json::to_string(&status.waybar())?,
+            BarFormat::Plain => status.plain(),
+            BarFormat::I3bar => serde_json::to_string(&status.i3bar())?,
+        };
+
+        Ok(content)
+    }
+
+    /// Render the lines that have to be printed for the given status.
+    pub fn render(&mut self, status: &impl BarOutput) -> Result<String> {
+        let content = self.content(status)?;
+        let output = match self.format {
+            BarFormat::Waybar | BarFormat::Plain => content,
+            BarFormat::I3bar => {
+                let blocks = content;
+                if self.started {
+                    format!(",{blocks}")
+                } else {
+                    let header = serde_json::to_string(&I3BarHeader {
+                        version: 1,
+                        click_events: self.click_events,
+                    })?;
+                    format!("{header}\n[\n{blocks}")
+                }
+            }
+        };
+        self.started = true;
+
+        Ok(output)
+    }
+
+    /// Print the status to stdout.
+    ///
+    /// Unlike `println!`, this returns an error instead of panicking if stdout has been closed.
+    pub fn print(&mut self, status: &impl BarOutput) -> Result<()> {
+        let output = self.render(status)?;
+
+        let mut stdout = stdout().lock();
+        writeln!(stdout, "{output}")?;
+        stdout.flush()?;
+
+        Ok(())
+    }
+}
+
+/// The mouse buttons a bar may report.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    ScrollUp,
+    ScrollDown,
+}
+
+/// A click event as sent by i3bar/swaybar on stdin.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ClickEvent {
+    /// Name and instance of the block that has been clicked.
+    pub name: Option<String>,
+    pub instance: Option<String>,
+    /// X11 button number, i.e. `1` is left and `4`/`5` are scroll up/down.
+    pub button: usize,
+    #[serde(default)]
+    pub modifiers: Vec<String>,
+}
+
+impl ClickEvent {
+    /// The clicked button, if it's one we know.
+    pub fn mouse_button(&self) -> Option<MouseButton> {
+        match self.button {
+            1 => Some(MouseButton::Left),
+            2 => Some(MouseButton::Middle),
+            3 => Some(MouseButton::Right),
+            4 => Some(MouseButton::ScrollUp),
+            5 => Some(MouseButton::ScrollDown),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a single line of the click event stream.
+///
+/// Just like the status output, click events are sent as an infinite JSON array with one event
+/// per line, so lines may be prefixed by the opening bracket or a separating comma.
+pub fn parse_click_event(line: &str) -> Option<ClickEvent> {
+    let line = line.trim().trim_start_matches(['[', ',']).trim();
+    if line.is_empty() {
+        return None;
+    }
+
+    match serde_json::from_str(line) {
+        Ok(event) => Some(event),
+        Err(err) => {
+            warn!("Failed to parse click event {line}: {err:?}");
+            None
+        }
+    }
+}
+
+/// Run a status producer in a loop and print its output whenever it changes.
+///
+/// The producer is called once per interval and whenever an event is received.
+/// This allows status binaries to stay alive instead of being re-spawned by the bar.
+pub struct StatusLoop {
+    printer: StatusPrinter,
+    interval: Duration,
+    sender: Sender<()>,
+    events: Receiver<()>,
+}
+
+impl StatusLoop {
+    pub fn new(printer: StatusPrinter, interval: Duration) -> Self {
+        let (sender, events) = channel();
+        Self {
+            printer,
+            interval,
+            sender,
+            events,
+        }
+    }
+
+    /// Get a sender that can be used to trigger a refresh of the status.
+    pub fn event_sender(&self) -> Sender<()> {
+        self.sender.clone()
+    }
+
+    /// Read click events from stdin and pass them to the handler.
+    /// The status is refreshed after each handled click.
+    pub fn on_click<Handler>(mut self, mut handler: Handler) -> Self
+    where
+        Handler: FnMut(ClickEvent) -> Result<()> + Send + 'static,
+    {
+        self.printer = self.printer.click_events(true);
+
+        let sender = self.event_sender();
+        spawn(move || {
+            for line in stdin().lock().lines() {
+                let Ok(line) = line else {
+                    return;
+                };
+                let Some(event) = parse_click_event(&line) else {
+                    continue;
+                };
+
+                debug!("Received click event: {event:?}");
+                if let Err(err) = handler(event) {
+                    error!("Failed to handle click event: {err:?}");
+                }
+                if sender.send(()).is_err() {
+                    return;
+                }
+            }
+        });
+
+        self
+    }
+
+    /// Run the loop until the bar closes our stdout.
+    ///
+    /// Errors of the producer are logged, as a single failed refresh shouldn't kill the module.
+    pub fn run<S: BarOutput>(mut self, mut producer: impl FnMut() -> Result<S>) -> Result<()> {
+        let mut last_content = None;
+
+        loop {
+            match producer() {
+                Ok(status) => {
+                    let content = self.printer.content(&status)?;
+                    if last_content.as_ref() != Some(&content) {
+                        match self.printer.print(&status) {
+                            Ok(()) => last_content = Some(content),
+                            // The bar went away, there's nobody left to talk to.
+                            Err(err) if is_broken_pipe(&err) => return Ok(()),
+                            Err(err) => return Err(err),
+                        }
+                    }
+                }
+                Err(err) => error!("Failed to get status: {err:?}"),
+            }
+
+            self.wait();
+
+            // Don't wait for the next change to notice that the bar is gone.
+            if stdout_closed() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Block until the next interval or until an event is received.
+    fn wait(&self) {
+        // We hold a sender ourselves, so the channel cannot disconnect.
+        if self.events.recv_timeout(self.interval).is_ok() {
+            // Drain all other pending events, a single refresh covers all of them.
+            while self.events.try_recv().is_ok() {}
+        }
+    }
+}
+
+/// Check whether an error has been caused by writing to a closed pipe.
+fn is_broken_pipe(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<io::Error>()
+        .is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe)
+}
+
+/// Check whether the reading end of our stdout has been closed.
+fn stdout_closed() -> bool {
+    let mut poll_fd = libc::pollfd {
+        fd: libc::STDOUT_FILENO,
+        events: 0,
+        revents: 0,
+    };
+
+    // Errors and hang-ups are always reported, even if we don't ask for any events.
+    // SAFETY: `poll` doesn't take ownership of stdout's fd and only reports on it, even if it has
+    // been closed. We pass a single `pollfd`, which matches the count of `1` and is exclusively
+    // borrowed for the call.
+    let result = unsafe { libc::poll(&mut poll_fd, 1, 0) };
+    result > 0 && poll_fd.revents & libc::POLLERR != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(text: &str, class: &str) -> CustomBarStatus {
+        let mut status = CustomBarStatus::new(text.into());
+        status.tooltip = "tooltip".into();
+        status.class = class.into();
+
+        status
+    }
+
+    #[test]
+    fn renders_waybar() -> Result<()> {
+        let mut printer = StatusPrinter::new(BarFormat::Waybar);
+        assert_eq!(
+            printer.render(&status("text", "good"))?,
+            r#"{"text":"text","tooltip":"tooltip","class":"good"}"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn renders_i3bar_protocol() -> Result<()> {
+        let mut printer = StatusPrinter::new(BarFormat::I3bar).click_events(true);
+
+        // The first status is prefixed with the header and the start of the infinite array.
+        assert_eq!(
+            printer.render(&status("text", "critical"))?,
+            "{\"version\":1,\"click_events\":true}\n[\n\
+            [{\"full_text\":\"text\",\"color\":\"#FF0000\",\"urgent\":true}]"
+        );
+
+        // All following statuses are simply appended to the array.
+        assert_eq!(printer.render(&status("", ""))?, ",[]");
+
+        Ok(())
+    }
+
+    #[test]
+    fn parses_click_events() {
+        assert_eq!(parse_click_event("["), None);
+
+        let event = parse_click_event(r#"{"name":"todo","button":1,"x":10,"y":5}"#).unwrap();
+        assert_eq!(event.name, Some("todo".into()));
+        assert_eq!(event.mouse_button(), Some(MouseButton::Left));
+
+        let event = parse_click_event(r#",{"button":5,"modifiers":["Shift"]}"#).unwrap();
+        assert_eq!(event.mouse_button(), Some(MouseButton::ScrollDown));
+        assert_eq!(event.modifiers, vec!["Shift".to_string()]);
+    }
+}
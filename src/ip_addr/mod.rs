@@ -0,0 +1,313 @@
+//! Interfaces, addresses and routes of the host.
+//!
+//! They're read from the kernel via rtnetlink. If that fails, we fall back to parsing the JSON
+//! output of `ip -j addr`, `ip -j link` and `ip -j route`, which is what the models mirror.
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use serde::Deserialize;
+
+use crate::exec::Cmd;
+
+mod netlink;
+
+pub use netlink::{NetlinkEvent, NetlinkMonitor};
+
+/// The lifetime `ip` reports for addresses that never expire.
+pub const INFINITE_LIFETIME: u64 = u32::MAX as u64;
+
+/// Get all interfaces with their addresses.
+pub fn get_interfaces() -> Result<Vec<Interface>> {
+    match netlink::interfaces() {
+        Ok(interfaces) => return Ok(interfaces),
+        Err(err) => debug!("Couldn't get interfaces via netlink, falling back to ip: {err:?}"),
+    }
+
+    let capture = Cmd::new("ip -j addr").run_success()?;
+    parse_interfaces(&capture.stdout_str())
+}
+
+pub fn get_links() -> Result<Vec<Link>> {
+    match netlink::links() {
+        Ok(links) => return Ok(links),
+        Err(err) => debug!("Couldn't get links via netlink, falling back to ip: {err:?}"),
+    }
+
+    let capture = Cmd::new("ip -j link").run_success()?;
+    parse_links(&capture.stdout_str())
+}
+
+/// Get the routes of the main table for both IPv4 and IPv6.
+pub fn get_routes() -> Result<Vec<Route>> {
+    match netlink::routes() {
+        Ok(routes) => return Ok(routes),
+        Err(err) => debug!("Couldn't get routes via netlink, falling back to ip: {err:?}"),
+    }
+
+    // A host without IPv6 (or IPv4) shouldn't lose the routes of the other family.
+    let mut routes = Vec::new();
+    let families = ["-4", "-6"];
+    let mut errors = Vec::new();
+    for family in families {
+        match family_routes(family) {
+            Ok(family_routes) => routes.extend(family_routes),
+            Err(err) => {
+                warn!("Couldn't get {family} routes via ip: {err:?}");
+                errors.push(err);
+            }
+        }
+    }
+
+    // Only fail if neither family could be read.
+    if errors.len() == families.len() {
+        return Err(errors.remove(0));
+    }
+
+    Ok(routes)
+}
+
+/// Get the routes of a single address family (`-4` or `-6`) via `ip`.
+fn family_routes(family: &str) -> Result<Vec<Route>> {
+    let capture = Cmd::new(format!("ip -j {family} route")).run_success()?;
+    parse_routes(&capture.stdout_str())
+}
+
+pub fn parse_interfaces(output: &str) -> Result<Vec<Interface>> {
+    serde_json::from_str(output).context("Failed to deserialize ip addr output")
+}
+
+pub fn parse_links(output: &str) -> Result<Vec<Link>> {
+    serde_json::from_str(output).context("Failed to deserialize ip link output")
+}
+
+pub fn parse_routes(output: &str) -> Result<Vec<Route>> {
+    serde_json::from_str(output).context("Failed to deserialize ip route output")
+}
+
+/// The name of the interface that carries the default route.
+pub fn default_route_interface() -> Result<Option<String>> {
+    let routes = get_routes()?;
+    Ok(default_route(&routes).and_then(|route| route.dev.clone()))
+}
+
+/// Get the default route with the lowest metric.
+/// IPv4 routes usually don't have a metric, so they're preferred over IPv6 routes.
+pub fn default_route(routes: &[Route]) -> Option<&Route> {
+    routes
+        .iter()
+        .filter(|route| route.is_default())
+        .min_by_key(|route| route.metric.unwrap_or_default())
+}
+
+/// The entry struct for `ip -j link` output.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Link {
+    pub ifindex: usize,
+    pub ifname: String,
+    pub flags: Vec<String>,
+    pub mtu: usize,
+    pub qdisc: Option<String>,
+    pub operstate: String,
+    pub linkmode: Option<String>,
+    pub group: Option<String>,
+    pub txqlen: Option<usize>,
+    pub link_type: String,
+    /// The hardware address.
+    pub address: Option<String>,
+    pub broadcast: Option<String>,
+    /// The bridge or bond this interface is part of.
+    pub master: Option<String>,
+}
+
+impl Link {
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|existing| existing == flag)
+    }
+
+    /// Whether the interface is enabled and has a carrier.
+    ///
+    /// The operstate isn't reliable for this, as it's `UNKNOWN` for many virtual interfaces.
+    pub fn is_up(&self) -> bool {
+        self.has_flag("UP") && self.has_flag("LOWER_UP") && self.operstate != "DOWN"
+    }
+}
+
+/// The entry struct for `ip -j addr` output.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Interface {
+    #[serde(flatten)]
+    pub link: Link,
+    #[serde(default)]
+    pub addr_info: Vec<AddrInfo>,
+}
+
+impl Interface {
+    /// All addresses that're reachable from outside of the host and link.
+    pub fn global_addresses(&self) -> impl Iterator<Item = &AddrInfo> {
+        self.addr_info.iter().filter(|addr| addr.is_global())
+    }
+
+    /// The address that's most useful to display.
+    /// Global addresses are preferred over local ones and IPv4 over IPv6.
+    pub fn preferred_address(&self) -> Option<&AddrInfo> {
+        let rank = |addr: &AddrInfo| (!addr.is_global(), !addr.is_ipv4());
+        self.addr_info
+            .iter()
+            .filter(|addr| addr.is_ipv4() || addr.is_ipv6())
+            .min_by_key(|addr| rank(addr))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddrInfo {
+    /// Either `inet` or `inet6`.
+    pub family: String,
+    pub local: String,
+    pub prefixlen: u8,
+    /// The peer of point-to-point links.
+    pub address: Option<String>,
+    pub metric: Option<usize>,
+    pub broadcast: Option<String>,
+    /// E.g. `global`, `link` or `host`.
+    pub scope: String,
+    /// The address has been configured via DHCP or SLAAC.
+    #[serde(default)]
+    pub dynamic: bool,
+    /// A privacy extension address.
+    #[serde(default)]
+    pub temporary: bool,
+    #[serde(default)]
+    pub deprecated: bool,
+    #[serde(default)]
+    pub tentative: bool,
+    #[serde(default)]
+    pub noprefixroute: bool,
+    pub label: Option<String>,
+    /// In seconds, [INFINITE_LIFETIME] for static addresses.
+    pub valid_life_time: u64,
+    pub preferred_life_time: u64,
+}
+
+impl AddrInfo {
+    pub fn is_ipv4(&self) -> bool {
+        self.family == "inet"
+    }
+
+    pub fn is_ipv6(&self) -> bool {
+        self.family == "inet6"
+    }
+
+    pub fn is_global(&self) -> bool {
+        self.scope == "global"
+    }
+}
+
+/// The entry struct for `ip -j route` output.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Route {
+    /// The destination, e.g. `default` or `192.168.1.0/24`.
+    pub dst: String,
+    /// E.g. `unreachable` or `blackhole`. Missing for normal unicast routes.
+    #[serde(rename = "type")]
+    pub route_type: Option<String>,
+    pub gateway: Option<String>,
+    /// Routes that don't send packets anywhere don't have a device.
+    pub dev: Option<String>,
+    pub protocol: Option<String>,
+    pub scope: Option<String>,
+    pub prefsrc: Option<String>,
+    pub metric: Option<usize>,
+    #[serde(default)]
+    pub flags: Vec<String>,
+    pub pref: Option<String>,
+}
+
+impl Route {
+    pub fn is_default(&self) -> bool {
+        self.dst == "default" && self.route_type.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IP_ADDR: &str = r#"[
+        {"ifindex":1,"ifname":"lo","flags":["LOOPBACK","UP","LOWER_UP"],"mtu":65536,
+         "qdisc":"noqueue","operstate":"UNKNOWN","group":"default","txqlen":1000,
+         "link_type":"loopback","address":"00:00:00:00:00:00","broadcast":"00:00:00:00:00:00",
+         "addr_info":[
+            {"family":"inet","local":"127.0.0.1","prefixlen":8,"scope":"host","label":"lo",
+             "valid_life_time":4294967295,"preferred_life_time":4294967295}]},
+        {"ifindex":2,"ifname":"enp5s0","flags":["NO-CARRIER","BROADCAST","MULTICAST","UP"],
+         "mtu":1500,"qdisc":"fq_codel","operstate":"DOWN","group":"default","txqlen":1000,
+         "link_type":"ether","address":"a8:a1:59:00:00:01","broadcast":"ff:ff:ff:ff:ff:ff",
+         "addr_info":[]},
+        {"ifindex":3,"ifname":"wlp3s0","flags":["BROADCAST","MULTICAST","UP","LOWER_UP"],
+         "mtu":1500,"qdisc":"noqueue","operstate":"UP","group":"default","txqlen":1000,
+         "link_type":"ether","address":"8c:c6:81:00:00:02","broadcast":"ff:ff:ff:ff:ff:ff",
+         "addr_info":[
+            {"family":"inet6","local":"fe80::8ec6:81ff:fe00:2","prefixlen":64,"scope":"link",
+             "noprefixroute":true,"valid_life_time":4294967295,"preferred_life_time":4294967295},
+            {"family":"inet6","local":"2001:db8::8ec6:81ff:fe00:2","prefixlen":64,"scope":"global",
+             "dynamic":true,"noprefixroute":true,"valid_life_time":86400,"preferred_life_time":14400},
+            {"family":"inet","local":"192.168.1.23","prefixlen":24,"broadcast":"192.168.1.255",
+             "scope":"global","dynamic":true,"noprefixroute":true,"label":"wlp3s0",
+             "valid_life_time":42000,"preferred_life_time":42000}]},
+        {"ifindex":4,"ifname":"wg0","flags":["POINTOPOINT","NOARP","UP","LOWER_UP"],"mtu":1420,
+         "qdisc":"noqueue","operstate":"UNKNOWN","group":"default","txqlen":1000,"link_type":"none",
+         "addr_info":[
+            {"family":"inet","local":"10.0.0.2","prefixlen":32,"scope":"global","label":"wg0",
+             "valid_life_time":4294967295,"preferred_life_time":4294967295}]}
+    ]"#;
+
+    const IP_ROUTE: &str = r#"[
+        {"dst":"default","gateway":"192.168.1.1","dev":"wlp3s0","protocol":"dhcp",
+         "prefsrc":"192.168.1.23","metric":600,"flags":[]},
+        {"dst":"default","gateway":"10.0.0.1","dev":"wg0","metric":50,"flags":[]},
+        {"type":"unreachable","dst":"default","metric":10,"flags":[]},
+        {"dst":"192.168.1.0/24","dev":"wlp3s0","protocol":"kernel","scope":"link",
+         "prefsrc":"192.168.1.23","metric":600,"flags":[]}
+    ]"#;
+
+    #[test]
+    fn parses_interfaces() -> Result<()> {
+        let interfaces = parse_interfaces(IP_ADDR)?;
+        let up: Vec<&str> = interfaces
+            .iter()
+            .filter(|interface| interface.link.is_up())
+            .map(|interface| interface.link.ifname.as_str())
+            .collect();
+        assert_eq!(up, ["lo", "wlp3s0", "wg0"]);
+
+        let wifi = &interfaces[2];
+        assert_eq!(wifi.link.address.as_deref(), Some("8c:c6:81:00:00:02"));
+        assert_eq!(wifi.global_addresses().count(), 2);
+        let address = wifi.preferred_address().unwrap();
+        assert_eq!(address.local, "192.168.1.23");
+        assert_eq!(address.prefixlen, 24);
+        assert!(address.dynamic);
+
+        // Host and link scoped addresses are still better than nothing.
+        assert_eq!(
+            interfaces[0].preferred_address().unwrap().local,
+            "127.0.0.1"
+        );
+        assert!(interfaces[1].preferred_address().is_none());
+        assert_eq!(
+            interfaces[3].addr_info[0].valid_life_time,
+            INFINITE_LIFETIME
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn finds_default_route() -> Result<()> {
+        let routes = parse_routes(IP_ROUTE)?;
+        let route = default_route(&routes).unwrap();
+        assert_eq!(route.dev.as_deref(), Some("wg0"));
+        assert_eq!(route.gateway.as_deref(), Some("10.0.0.1"));
+
+        Ok(())
+    }
+}
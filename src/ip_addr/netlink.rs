@@ -0,0 +1,719 @@
+//! A minimal rtnetlink client.
+//!
+//! Interfaces, addresses and routes are dumped directly from the kernel and converted into the
+//! same types the `ip -j` output is deserialized into. Values are formatted the way `ip` does it,
+//! so callers don't notice where the data came from.
+//!
+//! [NetlinkMonitor] subscribes to the multicast groups of rtnetlink, which allows to react to
+//! changes immediately instead of polling.
+use std::{
+    collections::HashMap,
+    io,
+    net::{Ipv4Addr, Ipv6Addr},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, bail};
+
+use super::{AddrInfo, INFINITE_LIFETIME, Interface, Link, Route};
+use crate::netlink::{NLMSG_HEADER_LEN, NetlinkSocket, parse_messages, read_u16, read_u32};
+
+/// Sizes of the headers of the rtnetlink messages we use.
+const IFINFOMSG_LEN: usize = 16;
+const IFADDRMSG_LEN: usize = 8;
+const RTMSG_LEN: usize = 12;
+
+/// The upper bits of attribute types are flags (`linux/netlink.h`).
+const NLA_TYPE_MASK: u16 = 0x3fff;
+
+/// `IFA_RT_PRIORITY` from `linux/if_addr.h`, which isn't part of libc.
+const IFA_RT_PRIORITY: u16 = 9;
+
+/// Flags of routes (`linux/rtnetlink.h`).
+const RTNH_F_ONLINK: u32 = 4;
+const RTNH_F_LINKDOWN: u32 = 16;
+
+/// The kernel answers dumps right away, this only prevents us from hanging forever.
+const DUMP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Interface flags in the order `ip` prints them.
+const LINK_FLAGS: &[(libc::c_int, &str)] = &[
+    (libc::IFF_LOOPBACK, "LOOPBACK"),
+    (libc::IFF_BROADCAST, "BROADCAST"),
+    (libc::IFF_POINTOPOINT, "POINTOPOINT"),
+    (libc::IFF_MULTICAST, "MULTICAST"),
+    (libc::IFF_NOARP, "NOARP"),
+    (libc::IFF_ALLMULTI, "ALLMULTI"),
+    (libc::IFF_PROMISC, "PROMISC"),
+    (libc::IFF_MASTER, "MASTER"),
+    (libc::IFF_SLAVE, "SLAVE"),
+    (libc::IFF_UP, "UP"),
+    (libc::IFF_LOWER_UP, "LOWER_UP"),
+    (libc::IFF_DORMANT, "DORMANT"),
+];
+
+/// A change that has been announced by the kernel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NetlinkEvent {
+    /// An interface has been added, removed or changed its state.
+    Link,
+    Address,
+    Route,
+    /// The socket buffer overflowed and events have been lost.
+    Overrun,
+}
+
+/// Get all interfaces with their addresses, like `ip addr`.
+pub fn interfaces() -> Result<Vec<Interface>> {
+    let mut socket = RouteSocket::new()?;
+    let links = dump_links(&mut socket)?;
+
+    let mut addresses: HashMap<u32, Vec<AddrInfo>> = HashMap::new();
+    for (message_type, payload) in socket.dump(libc::RTM_GETADDR, &[0; IFADDRMSG_LEN])? {
+        if message_type != libc::RTM_NEWADDR {
+            continue;
+        }
+        if let Some((index, address)) = parse_address(&payload) {
+            addresses.entry(index).or_default().push(address);
+        }
+    }
+
+    let interfaces = links
+        .into_iter()
+        .map(|link| Interface {
+            addr_info: addresses.remove(&(link.ifindex as u32)).unwrap_or_default(),
+            link,
+        })
+        .collect();
+
+    Ok(interfaces)
+}
+
+/// Get all interfaces without their addresses, like `ip link`.
+pub fn links() -> Result<Vec<Link>> {
+    dump_links(&mut RouteSocket::new()?)
+}
+
+/// Get the routes of the main table for both IPv4 and IPv6, like `ip route`.
+pub fn routes() -> Result<Vec<Route>> {
+    let mut socket = RouteSocket::new()?;
+    let names: HashMap<u32, String> = dump_links(&mut socket)?
+        .into_iter()
+        .map(|link| (link.ifindex as u32, link.ifname))
+        .collect();
+
+    let routes = socket
+        .dump(libc::RTM_GETROUTE, &[0; RTMSG_LEN])?
+        .into_iter()
+        .filter(|(message_type, _)| *message_type == libc::RTM_NEWROUTE)
+        .filter_map(|(_, payload)| parse_route(&payload, &names))
+        .collect();
+
+    Ok(routes)
+}
+
+fn dump_links(socket: &mut RouteSocket) -> Result<Vec<Link>> {
+    let mut links = Vec::new();
+    let mut masters = HashMap::new();
+    for (message_type, payload) in socket.dump(libc::RTM_GETLINK, &[0; IFINFOMSG_LEN])? {
+        if message_type != libc::RTM_NEWLINK {
+            continue;
+        }
+        if let Some((link, master)) = parse_link(&payload) {
+            if let Some(master) = master {
+                masters.insert(link.ifindex, master);
+            }
+            links.push(link);
+        }
+    }
+
+    // Masters are referenced by their index, but `ip` shows their name.
+    let names: HashMap<u32, String> = links
+        .iter()
+        .map(|link| (link.ifindex as u32, link.ifname.clone()))
+        .collect();
+    for link in links.iter_mut() {
+        link.master = masters
+            .get(&link.ifindex)
+            .and_then(|master| names.get(master).cloned());
+    }
+
+    Ok(links)
+}
+
+/// Subscribe to changes of interfaces, addresses and routes.
+pub struct NetlinkMonitor {
+    socket: NetlinkSocket,
+}
+
+impl NetlinkMonitor {
+    pub fn new() -> Result<Self> {
+        let groups = libc::RTMGRP_LINK
+            | libc::RTMGRP_IPV4_IFADDR
+            | libc::RTMGRP_IPV6_IFADDR
+            | libc::RTMGRP_IPV4_ROUTE
+            | libc::RTMGRP_IPV6_ROUTE;
+
+        Ok(Self {
+            socket: NetlinkSocket::new(libc::SOCK_RAW, libc::NETLINK_ROUTE, groups as u32)?,
+        })
+    }
+
+    /// Wait up to `timeout` for events and return all events that're available.
+    pub fn receive(&self, timeout: Duration) -> Result<Vec<NetlinkEvent>> {
+        let mut events = Vec::new();
+        if !self.socket.poll(timeout)? {
+            return Ok(events);
+        }
+
+        let mut buffer = vec![0u8; 65536];
+        loop {
+            let len = match self.socket.read(&mut buffer) {
+                Ok(Some(len)) => len,
+                Ok(None) => break,
+                Err(err) if err.raw_os_error() == Some(libc::ENOBUFS) => {
+                    events.push(NetlinkEvent::Overrun);
+                    continue;
+                }
+                Err(err) => return Err(err).context("Failed to read from netlink socket"),
+            };
+
+            for (message_type, _) in parse_messages(&buffer[..len]) {
+                let event = match message_type {
+                    libc::RTM_NEWLINK | libc::RTM_DELLINK => NetlinkEvent::Link,
+                    libc::RTM_NEWADDR | libc::RTM_DELADDR => NetlinkEvent::Address,
+                    libc::RTM_NEWROUTE | libc::RTM_DELROUTE => NetlinkEvent::Route,
+                    _ => continue,
+                };
+                if !events.contains(&event) {
+                    events.push(event);
+                }
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+/// A rtnetlink socket that's used to request dumps.
+struct RouteSocket {
+    socket: NetlinkSocket,
+    sequence: u32,
+}
+
+impl RouteSocket {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            socket: NetlinkSocket::new(libc::SOCK_RAW, libc::NETLINK_ROUTE, 0)?,
+            sequence: 0,
+        })
+    }
+
+    /// Request a dump of all objects of a kind and return the type and payload of all messages.
+    ///
+    /// `header` is the zeroed family specific header, which means "all address families".
+    fn dump(&mut self, request_type: u16, header: &[u8]) -> Result<Vec<(u16, Vec<u8>)>> {
+        self.sequence += 1;
+        let total_len = NLMSG_HEADER_LEN + header.len();
+        let flags = (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16;
+
+        let mut message = Vec::with_capacity(total_len);
+        message.extend_from_slice(&(total_len as u32).to_ne_bytes());
+        message.extend_from_slice(&request_type.to_ne_bytes());
+        message.extend_from_slice(&flags.to_ne_bytes());
+        message.extend_from_slice(&self.sequence.to_ne_bytes());
+        message.extend_from_slice(&0u32.to_ne_bytes());
+        message.extend_from_slice(header);
+
+        self.socket
+            .send(&message)
+            .context("Failed to send netlink request")?;
+
+        let deadline = Instant::now() + DUMP_TIMEOUT;
+        let mut buffer = vec![0u8; 65536];
+        let mut messages = Vec::new();
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || !self.socket.poll(remaining)? {
+                bail!("Didn't receive a complete netlink dump");
+            }
+
+            let Some(len) = self.socket.read(&mut buffer)? else {
+                continue;
+            };
+
+            for (message_type, payload) in parse_messages(&buffer[..len]) {
+                match message_type as libc::c_int {
+                    libc::NLMSG_DONE => return Ok(messages),
+                    libc::NLMSG_ERROR => {
+                        let error = read_u32(payload, 0).unwrap_or_default() as i32;
+                        // An error of `0` is an acknowledgement.
+                        if error != 0 {
+                            return Err(io::Error::from_raw_os_error(-error))
+                                .context("Netlink dump failed");
+                        }
+                    }
+                    _ => messages.push((message_type, payload.to_vec())),
+                }
+            }
+        }
+    }
+}
+
+/// Parse the attributes that follow the family specific header of a message.
+fn parse_attributes(buffer: &[u8]) -> HashMap<u16, &[u8]> {
+    let mut attributes = HashMap::new();
+    let mut offset = 0;
+
+    while let (Some(len), Some(kind)) = (read_u16(buffer, offset), read_u16(buffer, offset + 2)) {
+        let len = len as usize;
+        if len < 4 || offset + len > buffer.len() {
+            break;
+        }
+
+        attributes.insert(kind & NLA_TYPE_MASK, &buffer[offset + 4..offset + len]);
+
+        // Attributes are aligned to 4 bytes as well.
+        offset += (len + 3) & !3;
+    }
+
+    attributes
+}
+
+/// Convert a `RTM_NEWLINK` message into a link and the index of its master.
+fn parse_link(payload: &[u8]) -> Option<(Link, Option<u32>)> {
+    let link_type = read_u16(payload, 2)?;
+    let ifindex = read_u32(payload, 4)? as usize;
+    let flags = read_u32(payload, 8)?;
+    let attributes = parse_attributes(payload.get(IFINFOMSG_LEN..)?);
+    let u32_attribute = |kind: u16| attributes.get(&kind).and_then(|data| read_u32(data, 0));
+    let u8_attribute = |kind: u16| attributes.get(&kind).and_then(|data| data.first().copied());
+
+    // `ip` reports interfaces that're up but don't have a carrier.
+    let mut flag_names = Vec::new();
+    let is_up = flags & libc::IFF_UP as u32 != 0;
+    if is_up && flags & libc::IFF_RUNNING as u32 == 0 {
+        flag_names.push("NO-CARRIER".to_string());
+    }
+    flag_names.extend(
+        LINK_FLAGS
+            .iter()
+            .filter(|(flag, _)| flags & *flag as u32 != 0)
+            .map(|(_, name)| name.to_string()),
+    );
+
+    let operstate = match u8_attribute(libc::IFLA_OPERSTATE) {
+        Some(1) => "NOTPRESENT",
+        Some(2) => "DOWN",
+        Some(3) => "LOWERLAYERDOWN",
+        Some(4) => "TESTING",
+        Some(5) => "DORMANT",
+        Some(6) => "UP",
+        _ => "UNKNOWN",
+    };
+    let linkmode = u8_attribute(libc::IFLA_LINKMODE).map(|mode| match mode {
+        0 => "DEFAULT".to_string(),
+        1 => "DORMANT".to_string(),
+        mode => mode.to_string(),
+    });
+    let group = u32_attribute(libc::IFLA_GROUP).map(|group| match group {
+        0 => "default".to_string(),
+        group => group.to_string(),
+    });
+
+    let link = Link {
+        ifindex,
+        ifname: attributes
+            .get(&libc::IFLA_IFNAME)
+            .map(|data| read_string(data))?,
+        flags: flag_names,
+        mtu: u32_attribute(libc::IFLA_MTU).unwrap_or_default() as usize,
+        qdisc: attributes
+            .get(&libc::IFLA_QDISC)
+            .map(|data| read_string(data)),
+        operstate: operstate.to_string(),
+        linkmode,
+        group,
+        txqlen: u32_attribute(libc::IFLA_TXQLEN).map(|len| len as usize),
+        link_type: link_type_name(link_type),
+        address: attributes
+            .get(&libc::IFLA_ADDRESS)
+            .and_then(|data| format_hardware_address(data)),
+        broadcast: attributes
+            .get(&libc::IFLA_BROADCAST)
+            .and_then(|data| format_hardware_address(data)),
+        master: None,
+    };
+
+    Some((link, u32_attribute(libc::IFLA_MASTER)))
+}
+
+/// The names `ip` uses for the most common `ARPHRD_*` types (`linux/if_arp.h`).
+fn link_type_name(link_type: u16) -> String {
+    match link_type {
+        1 => "ether",
+        768 => "ipip",
+        769 => "tunnel6",
+        772 => "loopback",
+        776 => "sit",
+        778 => "gre",
+        801 => "ieee802.11",
+        823 => "gre6",
+        65534 => "none",
+        other => return format!("[{other}]"),
+    }
+    .to_string()
+}
+
+/// Convert a `RTM_NEWADDR` message into an address and the index of its interface.
+fn parse_address(payload: &[u8]) -> Option<(u32, AddrInfo)> {
+    let family = *payload.first()?;
+    let prefixlen = *payload.get(1)?;
+    let scope = *payload.get(3)?;
+    let index = read_u32(payload, 4)?;
+    let attributes = parse_attributes(payload.get(IFADDRMSG_LEN..)?);
+
+    // The flags attribute supersedes the flags in the header, which only has space for 8 flags.
+    let flags = attributes
+        .get(&libc::IFA_FLAGS)
+        .and_then(|data| read_u32(data, 0))
+        .unwrap_or(*payload.get(2)? as u32);
+    let format = |kind: u16| {
+        attributes
+            .get(&kind)
+            .and_then(|data| format_ip_address(family, data))
+    };
+
+    // `IFA_LOCAL` is the address of the interface, while `IFA_ADDRESS` is the peer of
+    // point-to-point links. If there's no peer, both are the same. IPv6 only uses `IFA_ADDRESS`.
+    let address = format(libc::IFA_ADDRESS);
+    let local = format(libc::IFA_LOCAL).or(address.clone())?;
+    let peer = address.filter(|address| *address != local);
+
+    // The cache info contains the preferred and the valid lifetime.
+    let cache_info = attributes.get(&libc::IFA_CACHEINFO);
+    let lifetime = |offset: usize| {
+        cache_info
+            .and_then(|data| read_u32(data, offset))
+            .map_or(INFINITE_LIFETIME, |lifetime| lifetime as u64)
+    };
+
+    let address = AddrInfo {
+        family: match family as libc::c_int {
+            libc::AF_INET => "inet",
+            libc::AF_INET6 => "inet6",
+            _ => return None,
+        }
+        .to_string(),
+        local,
+        prefixlen,
+        address: peer,
+        metric: attributes
+            .get(&IFA_RT_PRIORITY)
+            .and_then(|data| read_u32(data, 0))
+            .map(|metric| metric as usize),
+        broadcast: format(libc::IFA_BROADCAST),
+        scope: scope_name(scope),
+        dynamic: flags & libc::IFA_F_PERMANENT == 0,
+        // `IFA_F_TEMPORARY` shares its bit with `IFA_F_SECONDARY`, which is used by IPv4.
+        temporary: family as libc::c_int == libc::AF_INET6 && flags & libc::IFA_F_TEMPORARY != 0,
+        deprecated: flags & libc::IFA_F_DEPRECATED != 0,
+        tentative: flags & libc::IFA_F_TENTATIVE != 0,
+        noprefixroute: flags & libc::IFA_F_NOPREFIXROUTE != 0,
+        label: attributes
+            .get(&libc::IFA_LABEL)
+            .map(|data| read_string(data)),
+        valid_life_time: lifetime(4),
+        preferred_life_time: lifetime(0),
+    };
+
+    Some((index, address))
+}
+
+/// Convert a `RTM_NEWROUTE` message into a route.
+/// Only routes of the main table are returned, just like `ip route` does by default.
+fn parse_route(payload: &[u8], names: &HashMap<u32, String>) -> Option<Route> {
+    let family = *payload.first()?;
+    let dst_len = *payload.get(1)?;
+    let table = *payload.get(4)?;
+    let protocol = *payload.get(5)?;
+    let scope = *payload.get(6)?;
+    let route_type = *payload.get(7)?;
+    let flags = read_u32(payload, 8)?;
+    let attributes = parse_attributes(payload.get(RTMSG_LEN..)?);
+    let u32_attribute = |kind: u16| attributes.get(&kind).and_then(|data| read_u32(data, 0));
+    let format = |kind: u16| {
+        attributes
+            .get(&kind)
+            .and_then(|data| format_ip_address(family, data))
+    };
+
+    // The table in the header is too small for custom tables, hence the attribute.
+    let table = u32_attribute(libc::RTA_TABLE).unwrap_or(table as u32);
+    if table != libc::RT_TABLE_MAIN as u32 || flags & libc::RTM_F_CLONED != 0 {
+        return None;
+    }
+
+    // Host routes don't get a prefix length.
+    let host_len = if family as libc::c_int == libc::AF_INET {
+        32
+    } else {
+        128
+    };
+    let dst = match format(libc::RTA_DST) {
+        _ if dst_len == 0 => "default".to_string(),
+        Some(dst) if dst_len == host_len => dst,
+        Some(dst) => format!("{dst}/{dst_len}"),
+        None => return None,
+    };
+
+    let mut route_flags = Vec::new();
+    if flags & RTNH_F_ONLINK != 0 {
+        route_flags.push("onlink".to_string());
+    }
+    if flags & RTNH_F_LINKDOWN != 0 {
+        route_flags.push("linkdown".to_string());
+    }
+
+    Some(Route {
+        dst,
+        route_type: match route_type {
+            libc::RTN_UNICAST => None,
+            libc::RTN_LOCAL => Some("local".to_string()),
+            libc::RTN_BROADCAST => Some("broadcast".to_string()),
+            libc::RTN_MULTICAST => Some("multicast".to_string()),
+            libc::RTN_BLACKHOLE => Some("blackhole".to_string()),
+            libc::RTN_UNREACHABLE => Some("unreachable".to_string()),
+            libc::RTN_PROHIBIT => Some("prohibit".to_string()),
+            libc::RTN_THROW => Some("throw".to_string()),
+            other => Some(other.to_string()),
+        },
+        gateway: format(libc::RTA_GATEWAY),
+        dev: u32_attribute(libc::RTA_OIF).and_then(|index| names.get(&index).cloned()),
+        // `ip` doesn't show routes that have been added without a specific protocol.
+        protocol: match protocol {
+            libc::RTPROT_UNSPEC | libc::RTPROT_BOOT => None,
+            libc::RTPROT_REDIRECT => Some("redirect".to_string()),
+            libc::RTPROT_KERNEL => Some("kernel".to_string()),
+            libc::RTPROT_STATIC => Some("static".to_string()),
+            9 => Some("ra".to_string()),
+            16 => Some("dhcp".to_string()),
+            other => Some(other.to_string()),
+        },
+        // The global scope is the default and isn't shown by `ip` either.
+        scope: (scope != libc::RT_SCOPE_UNIVERSE).then(|| scope_name(scope)),
+        prefsrc: format(libc::RTA_PREFSRC),
+        metric: u32_attribute(libc::RTA_PRIORITY).map(|metric| metric as usize),
+        flags: route_flags,
+        pref: attributes
+            .get(&libc::RTA_PREF)
+            .and_then(|data| data.first())
+            .map(|pref| match pref {
+                0 => "medium".to_string(),
+                1 => "high".to_string(),
+                3 => "low".to_string(),
+                other => other.to_string(),
+            }),
+    })
+}
+
+/// The names of the `RT_SCOPE_*` values (`linux/rtnetlink.h`).
+fn scope_name(scope: u8) -> String {
+    match scope {
+        libc::RT_SCOPE_UNIVERSE => "global".to_string(),
+        libc::RT_SCOPE_SITE => "site".to_string(),
+        libc::RT_SCOPE_LINK => "link".to_string(),
+        libc::RT_SCOPE_HOST => "host".to_string(),
+        libc::RT_SCOPE_NOWHERE => "nowhere".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn format_ip_address(family: u8, data: &[u8]) -> Option<String> {
+    match family as libc::c_int {
+        libc::AF_INET => Some(Ipv4Addr::from(<[u8; 4]>::try_from(data).ok()?).to_string()),
+        libc::AF_INET6 => Some(Ipv6Addr::from(<[u8; 16]>::try_from(data).ok()?).to_string()),
+        _ => None,
+    }
+}
+
+fn format_hardware_address(data: &[u8]) -> Option<String> {
+    if data.is_empty() {
+        return None;
+    }
+
+    let bytes: Vec<String> = data.iter().map(|byte| format!("{byte:02x}")).collect();
+    Some(bytes.join(":"))
+}
+
+/// Read a NUL terminated string attribute.
+fn read_string(data: &[u8]) -> String {
+    let data = data.split(|byte| *byte == 0).next().unwrap_or_default();
+    String::from_utf8_lossy(data).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a message the way the kernel sends it.
+    fn message(message_type: u16, header: &[u8], attributes: &[(u16, &[u8])]) -> Vec<u8> {
+        let mut body = header.to_vec();
+        for (kind, data) in attributes {
+            body.extend_from_slice(&((4 + data.len()) as u16).to_ne_bytes());
+            body.extend_from_slice(&kind.to_ne_bytes());
+            body.extend_from_slice(data);
+            body.resize((body.len() + 3) & !3, 0);
+        }
+
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(&((NLMSG_HEADER_LEN + body.len()) as u32).to_ne_bytes());
+        buffer.extend_from_slice(&message_type.to_ne_bytes());
+        buffer.resize(NLMSG_HEADER_LEN, 0);
+        buffer.extend(body);
+
+        buffer
+    }
+
+    #[test]
+    fn parses_links() {
+        let flags = (libc::IFF_UP | libc::IFF_BROADCAST | libc::IFF_MULTICAST) as u32;
+        let mut header = vec![0, 0];
+        header.extend_from_slice(&1u16.to_ne_bytes());
+        header.extend_from_slice(&2u32.to_ne_bytes());
+        header.extend_from_slice(&flags.to_ne_bytes());
+        header.extend_from_slice(&0u32.to_ne_bytes());
+
+        let buffer = message(
+            libc::RTM_NEWLINK,
+            &header,
+            &[
+                (libc::IFLA_IFNAME, b"enp5s0\0"),
+                (libc::IFLA_MTU, &1500u32.to_ne_bytes()),
+                (libc::IFLA_OPERSTATE, &[2]),
+                (libc::IFLA_ADDRESS, &[0xa8, 0xa1, 0x59, 0, 0, 1]),
+                (libc::IFLA_MASTER, &5u32.to_ne_bytes()),
+            ],
+        );
+
+        let messages = parse_messages(&buffer);
+        assert_eq!(messages.len(), 1);
+        let (link, master) = parse_link(messages[0].1).unwrap();
+
+        assert_eq!(link.ifindex, 2);
+        assert_eq!(link.ifname, "enp5s0");
+        assert_eq!(link.mtu, 1500);
+        assert_eq!(link.link_type, "ether");
+        assert_eq!(link.address.as_deref(), Some("a8:a1:59:00:00:01"));
+        // The cable isn't plugged in.
+        assert_eq!(link.flags, ["NO-CARRIER", "BROADCAST", "MULTICAST", "UP"]);
+        assert_eq!(link.operstate, "DOWN");
+        assert!(!link.is_up());
+        assert_eq!(master, Some(5));
+    }
+
+    #[test]
+    fn parses_addresses() {
+        let mut header = vec![libc::AF_INET as u8, 24, 0, 0];
+        header.extend_from_slice(&3u32.to_ne_bytes());
+        let mut cache_info = Vec::new();
+        for value in [3600u32, 7200, 0, 0] {
+            cache_info.extend_from_slice(&value.to_ne_bytes());
+        }
+
+        let buffer = message(
+            libc::RTM_NEWADDR,
+            &header,
+            &[
+                (libc::IFA_ADDRESS, &[192, 168, 1, 23]),
+                (libc::IFA_LOCAL, &[192, 168, 1, 23]),
+                (libc::IFA_BROADCAST, &[192, 168, 1, 255]),
+                (libc::IFA_LABEL, b"wlp3s0\0"),
+                (libc::IFA_CACHEINFO, &cache_info),
+                (libc::IFA_FLAGS, &libc::IFA_F_NOPREFIXROUTE.to_ne_bytes()),
+            ],
+        );
+
+        let (index, address) = parse_address(parse_messages(&buffer)[0].1).unwrap();
+        assert_eq!(index, 3);
+        assert_eq!(address.family, "inet");
+        assert_eq!(address.local, "192.168.1.23");
+        assert_eq!(address.address, None);
+        assert_eq!(address.prefixlen, 24);
+        assert_eq!(address.broadcast.as_deref(), Some("192.168.1.255"));
+        assert_eq!(address.scope, "global");
+        assert_eq!(address.label.as_deref(), Some("wlp3s0"));
+        assert!(address.dynamic);
+        assert!(address.noprefixroute);
+        assert_eq!(address.preferred_life_time, 3600);
+        assert_eq!(address.valid_life_time, 7200);
+    }
+
+    #[test]
+    fn secondary_addresses_arent_temporary() {
+        let mut header = vec![libc::AF_INET as u8, 24, libc::IFA_F_SECONDARY as u8, 0];
+        header.extend_from_slice(&3u32.to_ne_bytes());
+        let buffer = message(
+            libc::RTM_NEWADDR,
+            &header,
+            &[(libc::IFA_LOCAL, &[192, 168, 1, 42])],
+        );
+
+        let (_, address) = parse_address(parse_messages(&buffer)[0].1).unwrap();
+        assert_eq!(address.local, "192.168.1.42");
+        assert!(!address.temporary);
+
+        // The same bit marks privacy addresses of IPv6.
+        let mut header = vec![libc::AF_INET6 as u8, 64, libc::IFA_F_TEMPORARY as u8, 0];
+        header.extend_from_slice(&3u32.to_ne_bytes());
+        let buffer = message(
+            libc::RTM_NEWADDR,
+            &header,
+            &[(
+                libc::IFA_ADDRESS,
+                &[0xfd, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+            )],
+        );
+
+        let (_, address) = parse_address(parse_messages(&buffer)[0].1).unwrap();
+        assert_eq!(address.local, "fd00::1");
+        assert!(address.temporary);
+    }
+
+    #[test]
+    fn parses_routes() {
+        let names = HashMap::from([(3, "wlp3s0".to_string())]);
+        let route_header = |dst_len: u8, table: u8| {
+            let mut header = vec![
+                libc::AF_INET as u8,
+                dst_len,
+                0,
+                0,
+                table,
+                16,
+                libc::RT_SCOPE_UNIVERSE,
+                libc::RTN_UNICAST,
+            ];
+            header.extend_from_slice(&0u32.to_ne_bytes());
+            header
+        };
+        let attributes: &[(u16, &[u8])] = &[
+            (libc::RTA_GATEWAY, &[192, 168, 1, 1]),
+            (libc::RTA_OIF, &3u32.to_ne_bytes()),
+            (libc::RTA_PRIORITY, &600u32.to_ne_bytes()),
+        ];
+
+        let buffer = message(libc::RTM_NEWROUTE, &route_header(0, 254), attributes);
+        let route = parse_route(parse_messages(&buffer)[0].1, &names).unwrap();
+        assert!(route.is_default());
+        assert_eq!(route.gateway.as_deref(), Some("192.168.1.1"));
+        assert_eq!(route.dev.as_deref(), Some("wlp3s0"));
+        assert_eq!(route.protocol.as_deref(), Some("dhcp"));
+        assert_eq!(route.scope, None);
+        assert_eq!(route.metric, Some(600));
+
+        // Routes of other tables are ignored.
+        let buffer = message(libc::RTM_NEWROUTE, &route_header(0, 255), attributes);
+        assert!(parse_route(parse_messages(&buffer)[0].1, &names).is_none());
+    }
+}
@@ -1,8 +1,12 @@
+pub mod battery;
 pub mod exec;
 pub mod fs;
+pub mod headsetcontrol;
 pub mod i3status;
 pub mod ip_addr;
 pub mod logging;
+mod netlink;
+pub mod network;
 pub mod notify;
 pub mod pipewire;
 pub mod process;
@@ -0,0 +1,191 @@
+//! The raw netlink socket handling, which is shared by all netlink protocols we speak.
+//!
+//! This only covers opening sockets, waiting for and reading datagrams and splitting them into
+//! messages. The protocol specific parts live with their users, such as the process connector
+//! and the rtnetlink client.
+use std::{
+    io,
+    mem,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+
+/// Size of the `nlmsghdr` header, which precedes every message.
+pub const NLMSG_HEADER_LEN: usize = 16;
+
+pub struct NetlinkSocket {
+    socket: OwnedFd,
+}
+
+impl NetlinkSocket {
+    /// Open a netlink socket for the given protocol, which is subscribed to the given multicast
+    /// groups.
+    pub fn new(socket_type: libc::c_int, protocol: libc::c_int, groups: u32) -> Result<Self> {
+        // SAFETY: `socket` only takes plain integers and doesn't touch any memory of ours.
+        let fd =
+            unsafe { libc::socket(libc::AF_NETLINK, socket_type | libc::SOCK_CLOEXEC, protocol) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error()).context("Failed to open netlink socket");
+        }
+        // SAFETY: `fd` is a valid descriptor that has just been opened and nothing else owns it,
+        // so the `OwnedFd` is the only place where it gets closed.
+        let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // SAFETY: `sockaddr_nl` only consists of integers, for which all zeroes are valid.
+        let mut address: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as u16;
+        address.nl_groups = groups;
+        // SAFETY: The fd is owned by `socket` and thereby open. The pointer and the length both
+        // describe `address`, which outlives the call.
+        let result = unsafe {
+            libc::bind(
+                socket.as_raw_fd(),
+                &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as u32,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error()).context("Failed to bind netlink socket");
+        }
+
+        Ok(Self { socket })
+    }
+
+    /// Send a complete message to the kernel.
+    pub fn send(&self, message: &[u8]) -> io::Result<()> {
+        // SAFETY: The fd is owned by `self` and thereby open. The kernel only reads
+        // `message.len()` bytes from the start of `message`, which is borrowed for the call.
+        let result = unsafe {
+            libc::send(
+                self.socket.as_raw_fd(),
+                message.as_ptr() as *const libc::c_void,
+                message.len(),
+                0,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Wait until the socket becomes readable.
+    /// Returns `false` if the timeout has been hit.
+    pub fn poll(&self, timeout: Duration) -> Result<bool> {
+        let mut poll_fd = libc::pollfd {
+            fd: self.socket.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = timeout.as_millis().min(i32::MAX as u128) as i32;
+
+        // SAFETY: The fd is owned by `self` and thereby open. We pass a single `pollfd`, which
+        // matches the count of `1` and is exclusively borrowed for the call.
+        let result = unsafe { libc::poll(&mut poll_fd, 1, timeout) };
+        if result < 0 {
+            let err = io::Error::last_os_error();
+            // We got interrupted by a signal, treat it like a timeout.
+            if err.kind() == io::ErrorKind::Interrupted {
+                return Ok(false);
+            }
+            return Err(err).context("Failed to poll netlink socket");
+        }
+
+        Ok(result > 0)
+    }
+
+    /// Read a single datagram without blocking.
+    /// Returns `None` if there's nothing left to read.
+    pub fn read(&self, buffer: &mut [u8]) -> io::Result<Option<usize>> {
+        // SAFETY: The fd is owned by `self` and thereby open. The length is exactly
+        // `buffer.len()`, so the kernel never writes past the end of the exclusively borrowed
+        // buffer. Datagrams that don't fit are truncated.
+        let result = unsafe {
+            libc::recv(
+                self.socket.as_raw_fd(),
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+                libc::MSG_DONTWAIT,
+            )
+        };
+        if result < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::WouldBlock {
+                return Ok(None);
+            }
+            return Err(err);
+        }
+
+        // Without `MSG_TRUNC`, the kernel never reports more than it wrote into the buffer.
+        Ok(Some((result as usize).min(buffer.len())))
+    }
+}
+
+/// Split a datagram into its netlink messages and return the type and payload of each.
+pub fn parse_messages(buffer: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut messages = Vec::new();
+    let mut offset = 0;
+
+    while let Some(len) = read_u32(buffer, offset) {
+        let len = len as usize;
+        if len < NLMSG_HEADER_LEN || offset + len > buffer.len() {
+            break;
+        }
+
+        if let Some(message_type) = read_u16(buffer, offset + 4) {
+            messages.push((
+                message_type,
+                &buffer[offset + NLMSG_HEADER_LEN..offset + len],
+            ));
+        }
+
+        // Messages are aligned to 4 bytes.
+        offset += (len + 3) & !3;
+    }
+
+    messages
+}
+
+pub fn read_u16(buffer: &[u8], offset: usize) -> Option<u16> {
+    let bytes = buffer.get(offset..offset + 2)?;
+    Some(u16::from_ne_bytes(bytes.try_into().ok()?))
+}
+
+pub fn read_u32(buffer: &[u8], offset: usize) -> Option<u32> {
+    let bytes = buffer.get(offset..offset + 4)?;
+    Some(u32::from_ne_bytes(bytes.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a message with the given type and payload.
+    fn message(message_type: u16, payload: &[u8]) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(&((NLMSG_HEADER_LEN + payload.len()) as u32).to_ne_bytes());
+        buffer.extend_from_slice(&message_type.to_ne_bytes());
+        buffer.resize(NLMSG_HEADER_LEN, 0);
+        buffer.extend_from_slice(payload);
+
+        buffer
+    }
+
+    #[test]
+    fn splits_messages() {
+        // The first payload isn't aligned, so the next message starts after the padding.
+        let mut buffer = message(1, &[1, 2, 3]);
+        buffer.push(0);
+        buffer.extend(message(2, &[4, 5, 6, 7]));
+        // Truncated messages are dropped.
+        buffer.extend_from_slice(&64u32.to_ne_bytes());
+
+        assert_eq!(
+            parse_messages(&buffer),
+            vec![(1, [1, 2, 3].as_slice()), (2, [4, 5, 6, 7].as_slice())]
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::{fs::read_to_string, path::PathBuf};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, de::Error};
+
+use super::InterfaceKind;
+
+/// The configuration of `netinfo`, located at `~/.config/netinfo.toml`.
+///
+/// Example:
+/// ```toml
+/// # Regexes of interface names that're always shown.
+/// include = ["^docker0$"]
+/// # Regexes of interface names that're never shown.
+/// exclude = ["^tailscale"]
+/// # Kinds of interfaces that aren't shown, unless they're explicitly included.
+/// # Defaults to loopback, bridge and virtual interfaces.
+/// exclude_kinds = ["loopback", "virtual"]
+/// # The share of a link's speed at which the link is considered busy or saturated.
+/// # Defaults to 0.7 and 0.9.
+/// saturation_warning = 0.5
+/// saturation_critical = 0.8
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetinfoConfig {
+    #[serde(default, deserialize_with = "deserialize_regexes")]
+    pub include: Vec<Regex>,
+    #[serde(default, deserialize_with = "deserialize_regexes")]
+    pub exclude: Vec<Regex>,
+    #[serde(default = "default_exclude_kinds")]
+    pub exclude_kinds: Vec<InterfaceKind>,
+    #[serde(default = "default_saturation_warning")]
+    pub saturation_warning: f64,
+    #[serde(default = "default_saturation_critical")]
+    pub saturation_critical: f64,
+}
+
+impl Default for NetinfoConfig {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            exclude_kinds: default_exclude_kinds(),
+            saturation_warning: default_saturation_warning(),
+            saturation_critical: default_saturation_critical(),
+        }
+    }
+}
+
+/// Container and VM networks aren't interesting for me.
+fn default_exclude_kinds() -> Vec<InterfaceKind> {
+    vec![
+        InterfaceKind::Loopback,
+        InterfaceKind::Bridge,
+        InterfaceKind::Virtual,
+    ]
+}
+
+fn default_saturation_warning() -> f64 {
+    0.7
+}
+
+fn default_saturation_critical() -> f64 {
+    0.9
+}
+
+fn deserialize_regexes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Regex>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|pattern| Regex::new(pattern).map_err(D::Error::custom))
+        .collect()
+}
+
+impl NetinfoConfig {
+    pub fn path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("netinfo.toml"))
+    }
+
+    /// Load the config file.
+    /// Returns the default config if there's no config file.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path().filter(|path| path.exists()) else {
+            return Ok(Self::default());
+        };
+
+        let content = read_to_string(&path).context(format!("Failed to read {path:?}"))?;
+        toml::from_str(&content).context(format!("Failed to deserialize {path:?}"))
+    }
+
+    /// Whether an interface should be shown.
+    /// Explicitly included interfaces are always shown.
+    pub fn is_shown(&self, name: &str, kind: InterfaceKind) -> bool {
+        if self.include.iter().any(|regex| regex.is_match(name)) {
+            return true;
+        }
+
+        !self.exclude.iter().any(|regex| regex.is_match(name))
+            && !self.exclude_kinds.contains(&kind)
+    }
+
+    /// The bar class of a link, based on how much of its capacity is used.
+    pub fn saturation_class(&self, utilization: f64) -> &'static str {
+        if utilization >= self.saturation_critical {
+            "critical"
+        } else if utilization >= self.saturation_warning {
+            "warning"
+        } else {
+            ""
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Classify network interfaces by the info the kernel exposes in `/sys/class/net`.
+use std::{
+    fs::{read_link, read_to_string},
+    path::Path,
+};
+
+use serde::Deserialize;
+use strum::Display;
+
+pub const SYS_CLASS_NET: &str = "/sys/class/net";
+
+/// `ARPHRD_ETHER` from `linux/if_arp.h`.
+const TYPE_ETHER: u32 = 1;
+/// `ARPHRD_LOOPBACK` from `linux/if_arp.h`.
+const TYPE_LOOPBACK: u32 = 772;
+
+/// Drivers of phones and other devices that share their connection via USB.
+const TETHERING_DRIVERS: &[&str] = &["rndis_host", "cdc_ether", "cdc_ncm", "ipheth"];
+
+#[derive(Deserialize, Display, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum InterfaceKind {
+    Loopback,
+    Ethernet,
+    Wireless,
+    UsbTethering,
+    #[serde(rename = "wireguard")]
+    #[strum(serialize = "wireguard")]
+    WireGuard,
+    /// Layer 3 tunnels, such as most VPNs.
+    Tun,
+    /// Layer 2 tunnels, e.g. of virtual machines.
+    Tap,
+    Bridge,
+    /// Interfaces without any hardware, such as `veth` pairs of containers.
+    Virtual,
+    Other,
+}
+
+impl InterfaceKind {
+    /// Whether this is a tunnel, i.e. a VPN.
+    pub fn is_tunnel(&self) -> bool {
+        matches!(
+            self,
+            InterfaceKind::WireGuard | InterfaceKind::Tun | InterfaceKind::Tap
+        )
+    }
+}
+
+/// Determine the kind of an interface.
+/// `sys_class_net` is usually [`SYS_CLASS_NET`].
+pub fn classify_interface(sys_class_net: &Path, name: &str) -> InterfaceKind {
+    let dir = sys_class_net.join(name);
+    let read = |file: &str| {
+        read_to_string(dir.join(file))
+            .ok()
+            .map(|value| value.trim().to_string())
+    };
+
+    let link_type = read("type").and_then(|link_type| link_type.parse::<u32>().ok());
+    if link_type == Some(TYPE_LOOPBACK) {
+        return InterfaceKind::Loopback;
+    }
+    if dir.join("wireless").exists() || dir.join("phy80211").exists() {
+        return InterfaceKind::Wireless;
+    }
+    if dir.join("bridge").exists() {
+        return InterfaceKind::Bridge;
+    }
+    // Tun and tap devices expose their flags, tap devices are ethernet-like.
+    if dir.join("tun_flags").exists() {
+        return match link_type {
+            Some(TYPE_ETHER) => InterfaceKind::Tap,
+            _ => InterfaceKind::Tun,
+        };
+    }
+
+    let devtype = read("uevent").and_then(|uevent| {
+        uevent
+            .lines()
+            .find_map(|line| line.strip_prefix("DEVTYPE=").map(str::to_string))
+    });
+    match devtype.as_deref() {
+        Some("wireguard") => return InterfaceKind::WireGuard,
+        Some("wlan") => return InterfaceKind::Wireless,
+        Some("bridge") => return InterfaceKind::Bridge,
+        _ => {}
+    }
+
+    // Interfaces without a device aren't backed by any hardware.
+    if read_link(dir.join("device")).is_err() {
+        return InterfaceKind::Virtual;
+    }
+
+    // Phones look like USB ethernet adapters, so we have to look at the driver.
+    let driver = read_link(dir.join("device/driver"))
+        .ok()
+        .and_then(|driver| Some(driver.file_name()?.to_string_lossy().to_string()));
+    if driver.is_some_and(|driver| TETHERING_DRIVERS.contains(&driver.as_str())) {
+        return InterfaceKind::UsbTethering;
+    }
+
+    match link_type {
+        Some(TYPE_ETHER) => InterfaceKind::Ethernet,
+        _ => InterfaceKind::Other,
+    }
+}
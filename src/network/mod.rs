@@ -0,0 +1,13 @@
+//! Information about network interfaces, which isn't available via `ip`.
+mod config;
+mod interface;
+mod traffic;
+mod wireless;
+
+pub use config::*;
+pub use interface::*;
+pub use traffic::*;
+pub use wireless::*;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,119 @@
+use std::{
+    fs::{create_dir_all, write},
+    os::unix::fs::symlink,
+    path::Path,
+};
+
+use anyhow::Result;
+use tempfile::TempDir;
+
+use super::*;
+
+/// Build a fake `/sys/class/net` entry.
+struct FakeInterface<'a> {
+    root: &'a Path,
+    name: &'a str,
+}
+
+impl<'a> FakeInterface<'a> {
+    fn new(root: &'a Path, name: &'a str, link_type: u32) -> Result<Self> {
+        create_dir_all(root.join(name))?;
+        write(root.join(name).join("type"), format!("{link_type}\n"))?;
+        write(
+            root.join(name).join("uevent"),
+            format!("INTERFACE={name}\nIFINDEX=4\n"),
+        )?;
+        Ok(Self { root, name })
+    }
+
+    fn file(self, file: &str, content: &str) -> Result<Self> {
+        write(self.root.join(self.name).join(file), content)?;
+        Ok(self)
+    }
+
+    fn dir(self, dir: &str) -> Result<Self> {
+        create_dir_all(self.root.join(self.name).join(dir))?;
+        Ok(self)
+    }
+
+    /// Link the interface to a device, which is handled by the given driver.
+    fn device(self, device: &str, driver: &str) -> Result<Self> {
+        let device_dir = self.root.join("devices").join(device);
+        create_dir_all(&device_dir)?;
+        let driver_dir = self.root.join("drivers").join(driver);
+        create_dir_all(&driver_dir)?;
+        symlink(&driver_dir, device_dir.join("driver"))?;
+        symlink(&device_dir, self.root.join(self.name).join("device"))?;
+        Ok(self)
+    }
+}
+
+#[test]
+fn classifies_interfaces() -> Result<()> {
+    let dir = TempDir::new()?;
+    let root = dir.path();
+
+    FakeInterface::new(root, "lo", 772)?;
+    FakeInterface::new(root, "enp5s0", 1)?.device("0000:05:00.0", "r8169")?;
+    FakeInterface::new(root, "wlp3s0", 1)?
+        .dir("wireless")?
+        .device("0000:03:00.0", "iwlwifi")?;
+    FakeInterface::new(root, "enp0s20u1", 1)?.device("usb1/1-1/1-1:1.0", "rndis_host")?;
+    FakeInterface::new(root, "wg0", 65534)?.file("uevent", "DEVTYPE=wireguard\nINTERFACE=wg0\n")?;
+    FakeInterface::new(root, "tun0", 65534)?.file("tun_flags", "0x1001\n")?;
+    FakeInterface::new(root, "tap0", 1)?.file("tun_flags", "0x1002\n")?;
+    FakeInterface::new(root, "docker0", 1)?.dir("bridge")?;
+    FakeInterface::new(root, "veth1a2b3c", 1)?;
+
+    let kinds: Vec<(&str, InterfaceKind)> = [
+        "lo",
+        "enp5s0",
+        "wlp3s0",
+        "enp0s20u1",
+        "wg0",
+        "tun0",
+        "tap0",
+        "docker0",
+        "veth1a2b3c",
+    ]
+    .into_iter()
+    .map(|name| (name, classify_interface(root, name)))
+    .collect();
+
+    assert_eq!(
+        kinds,
+        [
+            ("lo", InterfaceKind::Loopback),
+            ("enp5s0", InterfaceKind::Ethernet),
+            ("wlp3s0", InterfaceKind::Wireless),
+            ("enp0s20u1", InterfaceKind::UsbTethering),
+            ("wg0", InterfaceKind::WireGuard),
+            ("tun0", InterfaceKind::Tun),
+            ("tap0", InterfaceKind::Tap),
+            ("docker0", InterfaceKind::Bridge),
+            ("veth1a2b3c", InterfaceKind::Virtual),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn filters_interfaces() -> Result<()> {
+    let config: NetinfoConfig = toml::from_str(
+        r#"
+        include = ["^docker0$"]
+        exclude = ["^tailscale"]
+        "#,
+    )?;
+
+    assert!(config.is_shown("enp5s0", InterfaceKind::Ethernet));
+    assert!(config.is_shown("docker0", InterfaceKind::Bridge));
+    assert!(!config.is_shown("br-1234", InterfaceKind::Bridge));
+    assert!(!config.is_shown("tailscale0", InterfaceKind::Tun));
+    assert!(!config.is_shown("lo", InterfaceKind::Loopback));
+
+    assert!(toml::from_str::<NetinfoConfig>(r#"exclude_kinds = ["unknown"]"#).is_err());
+
+    Ok(())
+}
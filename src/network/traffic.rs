@@ -0,0 +1,229 @@
+//! Traffic counters of network interfaces.
+//!
+//! The kernel only exposes the total amount of bytes an interface transferred, so rates are
+//! calculated from the difference to the last sample. Samples are kept in the runtime dir, which
+//! allows rates to be calculated between separate invocations of short-lived status commands.
+use std::{
+    collections::HashMap,
+    fs::{read_to_string, write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use dirs::runtime_dir;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+/// The total amount of bytes an interface received and sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Counters {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// Read the counters of an interface.
+/// `sys_class_net` is usually [`super::SYS_CLASS_NET`].
+pub fn read_counters(sys_class_net: &Path, name: &str) -> Result<Counters> {
+    let read = |file: &str| -> Result<u64> {
+        let path = sys_class_net.join(name).join("statistics").join(file);
+        let content = read_to_string(&path).context(format!("Failed to read {path:?}"))?;
+        content
+            .trim()
+            .parse()
+            .context(format!("Failed to parse {path:?}"))
+    };
+
+    Ok(Counters {
+        rx_bytes: read("rx_bytes")?,
+        tx_bytes: read("tx_bytes")?,
+    })
+}
+
+/// The negotiated speed of a link in MBit/s.
+/// This is only known for wired links. Wireless and virtual interfaces report `-1` or nothing.
+pub fn link_speed_mbit(sys_class_net: &Path, name: &str) -> Option<f64> {
+    let speed: f64 = read_to_string(sys_class_net.join(name).join("speed"))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+
+    (speed > 0.0).then_some(speed)
+}
+
+/// The current rates and the totals of this session.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Throughput {
+    /// Bytes per second. Unknown until there's a previous sample.
+    pub rx_rate: Option<f64>,
+    pub tx_rate: Option<f64>,
+    /// Bytes since the start of the session.
+    pub rx_total: u64,
+    pub tx_total: u64,
+}
+
+impl Throughput {
+    /// How much of a link's capacity is used by the busier direction, where `1.0` is a
+    /// saturated link.
+    pub fn utilization(&self, link_speed_mbit: f64) -> Option<f64> {
+        let rate = self.rx_rate?.max(self.tx_rate?);
+        Some(rate * 8.0 / 1_000_000.0 / link_speed_mbit)
+    }
+
+    /// The current rates, e.g. `↓1.2 MiB/s ↑32.0 KiB/s`.
+    pub fn rates(&self) -> Option<String> {
+        let (rx, tx) = (self.rx_rate?, self.tx_rate?);
+        Some(format!(
+            "↓{}/s ↑{}/s",
+            humanize_bytes(rx),
+            humanize_bytes(tx)
+        ))
+    }
+
+    /// The session totals, e.g. `↓1.5 GiB ↑200.0 MiB`.
+    pub fn totals(&self) -> String {
+        format!(
+            "↓{} ↑{}",
+            humanize_bytes(self.rx_total as f64),
+            humanize_bytes(self.tx_total as f64)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Sample {
+    /// Unix timestamp in milliseconds.
+    timestamp: i64,
+    counters: Counters,
+    /// The counters at the start of the session.
+    session_start: Counters,
+}
+
+/// The last sample of each interface.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrafficState {
+    samples: HashMap<String, Sample>,
+}
+
+impl TrafficState {
+    pub fn path() -> Result<PathBuf> {
+        Ok(runtime_dir()
+            .context("Couldn't find runtime dir")?
+            .join("netinfo-traffic.json"))
+    }
+
+    /// Load the state from the runtime dir.
+    /// Missing or broken state files just result in a fresh state.
+    pub fn load() -> Result<Self> {
+        let content = match read_to_string(Self::path()?) {
+            Ok(content) => content,
+            Err(_) => return Ok(Self::default()),
+        };
+
+        Ok(serde_json::from_str(&content).unwrap_or_else(|err| {
+            debug!("Ignoring broken traffic state: {err}");
+            Self::default()
+        }))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        write(&path, serde_json::to_string(self)?).context(format!("Failed to write {path:?}"))
+    }
+
+    /// Record a new sample of an interface and calculate its throughput.
+    ///
+    /// `timestamp` is a unix timestamp in milliseconds.
+    pub fn update(&mut self, name: &str, counters: Counters, timestamp: i64) -> Throughput {
+        let previous = self.samples.get(name).copied();
+
+        // Counters start from zero if an interface is re-created, which also starts a new session.
+        let previous = previous.filter(|previous| {
+            counters.rx_bytes >= previous.counters.rx_bytes
+                && counters.tx_bytes >= previous.counters.tx_bytes
+        });
+
+        let session_start = previous.map_or(counters, |previous| previous.session_start);
+        let elapsed = previous
+            .map(|previous| (timestamp - previous.timestamp) as f64 / 1000.0)
+            .filter(|elapsed| *elapsed > 0.0);
+        let rate = |current: u64, last: u64| Some((current - last) as f64 / elapsed?);
+
+        let throughput = Throughput {
+            rx_rate: previous
+                .and_then(|previous| rate(counters.rx_bytes, previous.counters.rx_bytes)),
+            tx_rate: previous
+                .and_then(|previous| rate(counters.tx_bytes, previous.counters.tx_bytes)),
+            rx_total: counters.rx_bytes - session_start.rx_bytes,
+            tx_total: counters.tx_bytes - session_start.tx_bytes,
+        };
+
+        self.samples.insert(
+            name.to_string(),
+            Sample {
+                timestamp,
+                counters,
+                session_start,
+            },
+        );
+
+        throughput
+    }
+
+    /// Forget all interfaces except the given ones.
+    pub fn retain(&mut self, names: &[String]) {
+        self.samples.retain(|name, _| names.contains(name));
+    }
+}
+
+/// Format an amount of bytes with a binary unit, e.g. `1.5 KiB`.
+pub fn humanize_bytes(bytes: f64) -> String {
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{value:.0} {}", UNITS[unit])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculates_throughput() {
+        let mut state = TrafficState::default();
+        let counters = |rx_bytes, tx_bytes| Counters { rx_bytes, tx_bytes };
+
+        let first = state.update("enp5s0", counters(1000, 500), 10_000);
+        assert_eq!(first.rx_rate, None);
+        assert_eq!((first.rx_total, first.tx_total), (0, 0));
+
+        let second = state.update("enp5s0", counters(11_000, 2500), 12_000);
+        assert_eq!(second.rx_rate, Some(5000.0));
+        assert_eq!(second.tx_rate, Some(1000.0));
+        assert_eq!((second.rx_total, second.tx_total), (10_000, 2000));
+        // 5000 B/s are 0.04 MBit/s.
+        assert_eq!(second.utilization(0.08), Some(0.5));
+
+        // The interface has been re-created and its counters were reset.
+        let third = state.update("enp5s0", counters(200, 100), 14_000);
+        assert_eq!(third.rx_rate, None);
+        assert_eq!((third.rx_total, third.tx_total), (0, 0));
+    }
+
+    #[test]
+    fn humanizes_bytes() {
+        assert_eq!(humanize_bytes(512.0), "512 B");
+        assert_eq!(humanize_bytes(1536.0), "1.5 KiB");
+        assert_eq!(humanize_bytes(3.0 * 1024.0 * 1024.0 * 1024.0), "3.0 GiB");
+    }
+}
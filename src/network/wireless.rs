@@ -0,0 +1,215 @@
+//! Signal quality and connection info of wireless interfaces.
+//!
+//! The signal is read from `/proc/net/wireless`, everything else from `iw dev $interface link`.
+use std::{collections::HashMap, fs::read_to_string};
+
+use log::{debug, warn};
+
+use crate::exec::Cmd;
+
+/// The received signal of a wireless interface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WirelessSignal {
+    /// The link quality, usually out of 70.
+    pub link_quality: f64,
+    /// The signal level in dBm.
+    pub level_dbm: f64,
+}
+
+/// Info about the network a wireless interface is connected to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WirelessLink {
+    pub ssid: Option<String>,
+    pub frequency_mhz: Option<f64>,
+    pub signal_dbm: Option<f64>,
+    /// E.g. `866.7 MBit/s`.
+    pub rx_bitrate: Option<String>,
+    pub tx_bitrate: Option<String>,
+}
+
+impl WirelessLink {
+    /// The frequency band of the connection, such as `5 GHz`.
+    pub fn band(&self) -> Option<&'static str> {
+        match self.frequency_mhz? as u32 {
+            2400..=2500 => Some("2.4 GHz"),
+            4900..=5900 => Some("5 GHz"),
+            5925..=7125 => Some("6 GHz"),
+            _ => None,
+        }
+    }
+
+    /// The speed of the link in MBit/s, i.e. the faster of both bitrates.
+    pub fn speed_mbit(&self) -> Option<f64> {
+        let parse = |bitrate: &Option<String>| -> Option<f64> {
+            bitrate.as_ref()?.split_whitespace().next()?.parse().ok()
+        };
+
+        match (parse(&self.rx_bitrate), parse(&self.tx_bitrate)) {
+            (Some(rx), Some(tx)) => Some(rx.max(tx)),
+            (rx, tx) => rx.or(tx),
+        }
+    }
+}
+
+/// Read the signal of all wireless interfaces.
+pub fn read_wireless_signals() -> HashMap<String, WirelessSignal> {
+    match read_to_string("/proc/net/wireless") {
+        Ok(content) => parse_proc_wireless(&content),
+        Err(err) => {
+            debug!("Failed to read /proc/net/wireless: {err}");
+            HashMap::new()
+        }
+    }
+}
+
+/// Parse `/proc/net/wireless`, which looks like this:
+/// ```text
+/// Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE
+///  face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22
+/// wlan0: 0000   54.  -56.  -256        0      0      0      0      0        0
+/// ```
+pub fn parse_proc_wireless(content: &str) -> HashMap<String, WirelessSignal> {
+    let mut signals = HashMap::new();
+    // The first two lines are the header.
+    for line in content.lines().skip(2) {
+        let Some((interface, values)) = line.split_once(':') else {
+            continue;
+        };
+        let values: Vec<&str> = values.split_whitespace().collect();
+        let parse =
+            |index: usize| -> Option<f64> { values.get(index)?.trim_end_matches('.').parse().ok() };
+
+        let (Some(link_quality), Some(level_dbm)) = (parse(1), parse(2)) else {
+            continue;
+        };
+        signals.insert(
+            interface.trim().to_string(),
+            WirelessSignal {
+                link_quality,
+                level_dbm,
+            },
+        );
+    }
+
+    signals
+}
+
+/// Get info about the network the interface is connected to.
+pub fn wireless_link(interface: &str) -> Option<WirelessLink> {
+    let capture = match Cmd::new(format!("iw dev {interface} link")).run_success() {
+        Ok(capture) => capture,
+        Err(err) => {
+            warn!("Got error reading link info of {interface}: {err:#?}");
+            return None;
+        }
+    };
+
+    parse_iw_link(&capture.stdout_str())
+}
+
+/// Parse the output of `iw dev $interface link`, which looks like this:
+/// ```text
+/// Connected to aa:bb:cc:dd:ee:ff (on wlan0)
+///         SSID: MyNetwork
+///         freq: 5180.0
+///         signal: -56 dBm
+///         rx bitrate: 866.7 MBit/s VHT-MCS 9 80MHz short GI VHT-NSS 2
+///         tx bitrate: 780.0 MBit/s VHT-MCS 8 80MHz short GI VHT-NSS 2
+/// ```
+/// Returns `None` if the interface isn't connected.
+pub fn parse_iw_link(output: &str) -> Option<WirelessLink> {
+    if !output.starts_with("Connected") {
+        return None;
+    }
+
+    // Bitrates are followed by the modulation details, which we're not interested in.
+    let bitrate = |value: &str| {
+        let mut parts = value.split_whitespace();
+        Some(format!("{} {}", parts.next()?, parts.next()?))
+    };
+
+    let mut link = WirelessLink::default();
+    for line in output.lines() {
+        let Some((key, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key {
+            "SSID" => link.ssid = Some(value.to_string()),
+            "freq" => link.frequency_mhz = value.parse().ok(),
+            "signal" => link.signal_dbm = value.trim_end_matches(" dBm").parse().ok(),
+            "rx bitrate" => link.rx_bitrate = bitrate(value),
+            "tx bitrate" => link.tx_bitrate = bitrate(value),
+            _ => {}
+        }
+    }
+
+    Some(link)
+}
+
+/// Map a signal level to bars.
+///
+/// - `-30 dBm` Maximum signal strength, you are probably standing right next to the access point.
+/// - `-50 dBm` Anything down to this level can be regarded as excellent signal strength.
+/// - `-60 dBm` This is still good, reliable signal strength.
+/// - `-67 dBm` This is the minimum value for all services that require smooth and reliable data
+///   traffic, such as VoIP or video streaming.
+/// - `-70 dBm` The signal is not very strong, but mostly sufficient for web, email and the like.
+/// - `-80 dBm` Minimum value required to make a connection. You cannot count on a reliable
+///   connection or sufficient signal strength to use services at this level.
+/// - `-90 dBm` It is very unlikely that you will be able to connect or make use of any services
+///   with this signal strength.
+pub fn signal_bars(level_dbm: f64) -> &'static str {
+    match level_dbm {
+        level if level >= -50.0 => "▇",
+        level if level >= -60.0 => "▆",
+        level if level >= -67.0 => "▅",
+        level if level >= -70.0 => "▃",
+        level if level >= -80.0 => "▁",
+        _ => "!",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_proc_wireless() {
+        let content =
+            "Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE
+ face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22
+wlp3s0: 0000   54.  -56.  -256        0      0      0      0      0        0
+";
+        let signals = parse_proc_wireless(content);
+        assert_eq!(
+            signals.get("wlp3s0"),
+            Some(&WirelessSignal {
+                link_quality: 54.0,
+                level_dbm: -56.0
+            })
+        );
+        assert_eq!(signal_bars(-56.0), "▆");
+    }
+
+    #[test]
+    fn parses_iw_link() {
+        let output = "Connected to aa:bb:cc:dd:ee:ff (on wlp3s0)
+	SSID: MyNetwork
+	freq: 5180.0
+	RX: 123456 bytes (789 packets)
+	signal: -56 dBm
+	rx bitrate: 866.7 MBit/s VHT-MCS 9 80MHz short GI VHT-NSS 2
+	tx bitrate: 780.0 MBit/s VHT-MCS 8 80MHz short GI VHT-NSS 2
+";
+        let link = parse_iw_link(output).unwrap();
+        assert_eq!(link.ssid.as_deref(), Some("MyNetwork"));
+        assert_eq!(link.band(), Some("5 GHz"));
+        assert_eq!(link.signal_dbm, Some(-56.0));
+        assert_eq!(link.rx_bitrate.as_deref(), Some("866.7 MBit/s"));
+        assert_eq!(link.tx_bitrate.as_deref(), Some("780.0 MBit/s"));
+        assert_eq!(link.speed_mbit(), Some(866.7));
+
+        assert_eq!(parse_iw_link("Not connected."), None);
+    }
+}
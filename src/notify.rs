@@ -1,4 +1,7 @@
+use std::fs::{read_to_string, write};
+
 use anyhow::{Context, Result};
+use dirs::runtime_dir;
 
 use crate::exec::Cmd;
 
@@ -6,8 +9,9 @@ use crate::exec::Cmd;
 pub fn critical_notify(display_time: usize, message: String) -> Result<()> {
     // Inform the user about the sink we just switched to.
     Cmd::new(format!(
-        "notify-send --urgency=critical --expire-time={display_time} '{message}'",
+        "notify-send --urgency=critical --expire-time={display_time}"
     ))
+    .arg(message)
     .run_success()
     .context("Failed to send notification.")?;
 
@@ -17,11 +21,46 @@ pub fn critical_notify(display_time: usize, message: String) -> Result<()> {
 /// Send a notification to the notification daemon.
 pub fn notify(display_time: usize, message: String) -> Result<()> {
     // Inform the user about the sink we just switched to.
-    Cmd::new(format!(
-        "notify-send --expire-time={display_time} '{message}'",
-    ))
-    .run_success()
-    .context("Failed to send notification.")?;
+    Cmd::new(format!("notify-send --expire-time={display_time}"))
+        .arg(message)
+        .run_success()
+        .context("Failed to send notification.")?;
+
+    Ok(())
+}
+
+/// Send a notification that replaces the last notification with the same key.
+///
+/// The id of the last notification is remembered in the runtime dir, so this also works across
+/// invocations. If a progress value (0-100) is given, most daemons display it as a bar.
+pub fn replaceable_notify(
+    display_time: usize,
+    message: String,
+    key: &str,
+    progress: Option<usize>,
+) -> Result<()> {
+    let id_path = runtime_dir()
+        .context("Couldn't find runtime dir")?
+        .join(format!("notify-{key}-id"));
+
+    let mut command = format!("notify-send --print-id --expire-time={display_time}");
+    let last_id = read_to_string(&id_path)
+        .ok()
+        .and_then(|id| id.trim().parse::<u32>().ok());
+    if let Some(id) = last_id {
+        command.push_str(&format!(" --replace-id={id}"));
+    }
+    if let Some(progress) = progress {
+        command.push_str(&format!(" --hint=int:value:{progress}"));
+    }
+
+    // Only the id must end up in the file, not any warnings.
+    let capture = Cmd::new(command)
+        .arg(message)
+        .separate_stderr()
+        .run_success()
+        .context("Failed to send notification.")?;
+    write(&id_path, capture.stdout_str().trim()).context("Failed to remember notification id")?;
 
     Ok(())
 }
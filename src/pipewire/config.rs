@@ -0,0 +1,180 @@
+use std::{fs::read_to_string, path::PathBuf};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, de::Error};
+
+use super::schema::node::Node;
+
+/// A rule that always routes the streams of an application to a specific sink.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteRule {
+    /// Case-insensitive substring of the application's name or binary.
+    pub application: String,
+    /// The alias or the beginning of the target sink's description.
+    pub sink: String,
+}
+
+/// Match nodes by their properties.
+///
+/// All given properties have to match. A matcher without any properties matches nothing.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NodeMatcher {
+    /// The exact `node.name`, e.g. `alsa_output.usb-Topping_D10s-00.analog-stereo`.
+    pub name: Option<String>,
+    /// A regex that's matched against the `node.description`.
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub description: Option<Regex>,
+    /// The exact `device.api`, e.g. `alsa` or `bluez5`.
+    pub api: Option<String>,
+}
+
+impl NodeMatcher {
+    pub fn matches(&self, node: &Node) -> bool {
+        let props = &node.info.props;
+        if self.name.is_none() && self.description.is_none() && self.api.is_none() {
+            return false;
+        }
+
+        self.name
+            .as_ref()
+            .is_none_or(|name| *name == props.node_name)
+            && self
+                .description
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(&props.node_description))
+            && self
+                .api
+                .as_ref()
+                .is_none_or(|api| Some(api) == props.device_api.as_ref())
+    }
+}
+
+/// A friendly name for a sink, which can be used instead of its description.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SinkAlias {
+    pub alias: String,
+    #[serde(rename = "match")]
+    pub matcher: NodeMatcher,
+}
+
+/// The configuration for switching sinks, located at `~/.config/change_sink.toml`.
+///
+/// Example:
+/// ```toml
+/// # Sinks that're never used. Defaults to the S/PDIF output of USB devices.
+/// ignored = [{ api = "alsa", description = "HDMI" }]
+/// # Sinks are rotated in this order. Sinks that aren't matched come last.
+/// order = [{ name = "alsa_output.usb-Topping_D10s-00.analog-stereo" }, { api = "bluez5" }]
+/// # The sink that's used by `change_sink built-in`. Defaults to descriptions starting with
+/// # "Built-in".
+/// built_in = { name = "alsa_output.pci-0000_00_1f.3.analog-stereo" }
+///
+/// [[aliases]]
+/// alias = "dac"
+/// match = { description = "^D10s" }
+///
+/// [[routes]]
+/// application = "discord"
+/// sink = "Arctis Nova 7"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SinkConfig {
+    #[serde(default = "default_ignored")]
+    pub ignored: Vec<NodeMatcher>,
+    #[serde(default)]
+    pub aliases: Vec<SinkAlias>,
+    #[serde(default)]
+    pub order: Vec<NodeMatcher>,
+    #[serde(default)]
+    pub routes: Vec<RouteRule>,
+    #[serde(default = "default_built_in")]
+    pub built_in: NodeMatcher,
+}
+
+impl Default for SinkConfig {
+    fn default() -> Self {
+        Self {
+            ignored: default_ignored(),
+            aliases: Vec::new(),
+            order: Vec::new(),
+            routes: Vec::new(),
+            built_in: default_built_in(),
+        }
+    }
+}
+
+/// Some sinks are just uninteresting for me.
+fn default_ignored() -> Vec<NodeMatcher> {
+    vec![NodeMatcher {
+        // Sony/Philips Digital Interface
+        // Coaxial/Optical input
+        description: Some(Regex::new("^USB Audio S/PDIF Output$").unwrap()),
+        ..Default::default()
+    }]
+}
+
+fn default_built_in() -> NodeMatcher {
+    NodeMatcher {
+        description: Some(Regex::new("^Built-in").unwrap()),
+        ..Default::default()
+    }
+}
+
+fn deserialize_regex<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Regex>, D::Error> {
+    let Some(pattern) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+
+    Regex::new(&pattern).map(Some).map_err(D::Error::custom)
+}
+
+impl SinkConfig {
+    pub fn path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("change_sink.toml"))
+    }
+
+    /// Load the config file.
+    /// Returns the default config if there's no config file.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path().filter(|path| path.exists()) else {
+            return Ok(Self::default());
+        };
+
+        let content = read_to_string(&path).context(format!("Failed to read {path:?}"))?;
+        toml::from_str(&content).context(format!("Failed to deserialize {path:?}"))
+    }
+
+    pub fn is_ignored(&self, node: &Node) -> bool {
+        self.ignored.iter().any(|matcher| matcher.matches(node))
+    }
+
+    /// Get the configured alias of a node, if there's any.
+    pub fn alias(&self, node: &Node) -> Option<&str> {
+        self.aliases
+            .iter()
+            .find(|alias| alias.matcher.matches(node))
+            .map(|alias| alias.alias.as_str())
+    }
+
+    /// The name that's shown to the user, i.e. the alias or the description.
+    pub fn display_name<'a>(&'a self, node: &'a Node) -> &'a str {
+        self.alias(node)
+            .unwrap_or(node.info.props.node_description.as_str())
+    }
+
+    /// The position of a node in the rotation order.
+    /// Nodes that aren't mentioned in the order are put at the end.
+    pub fn order_position(&self, node: &Node) -> usize {
+        self.order
+            .iter()
+            .position(|matcher| matcher.matches(node))
+            .unwrap_or(self.order.len())
+    }
+}
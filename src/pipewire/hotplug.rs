@@ -0,0 +1,137 @@
+//! Automatically switch sinks when devices are connected or disconnected.
+use std::{
+    collections::HashSet,
+    io::{BufRead, BufReader},
+    process::{Command, Stdio},
+    sync::mpsc::channel,
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail};
+use log::{debug, info, warn};
+
+use super::{
+    config::SinkConfig,
+    schema::{node::Node, parse_pw_dump},
+    sink::{filter_sinks, switch_sink},
+};
+
+/// Devices usually show up with a burst of graph updates.
+/// Wait this long after the last update before looking at the graph.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Watch the pipewire graph and switch to the sink with the highest priority whenever a sink
+/// becomes available. If a sink disappears, we make sure that the best remaining one is used.
+///
+/// The priority is determined by the `order` in the config file.
+/// Sinks that aren't mentioned there never cause a switch when they appear.
+pub fn watch_sinks() -> Result<()> {
+    let config = SinkConfig::load()?;
+
+    // `pw-dump --monitor` prints a JSON array for every batch of changes to the graph.
+    // We only use it as a trigger and look at the whole graph afterwards.
+    let mut child = Command::new("pw-dump")
+        .args(["--monitor", "--no-colors"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to spawn pw-dump --monitor")?;
+    let stdout = child
+        .stdout
+        .take()
+        .context("Failed to get pw-dump stdout")?;
+
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.starts_with('[') && sender.send(()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut known = sink_names(&filter_sinks(&parse_pw_dump()?, &config));
+    info!("Watching for sink changes. Known sinks: {known:?}");
+
+    while receiver.recv().is_ok() {
+        while receiver.recv_timeout(SETTLE_TIME).is_ok() {}
+
+        match handle_graph_change(&config, &known) {
+            Ok(sinks) => known = sinks,
+            Err(err) => warn!("Failed to handle graph change: {err:?}"),
+        }
+    }
+
+    let status = child.wait()?;
+    bail!("pw-dump --monitor exited with {status}");
+}
+
+/// Check the current graph for sinks that appeared or disappeared and switch if necessary.
+/// Returns the names of all currently available sinks.
+fn handle_graph_change(config: &SinkConfig, known: &HashSet<String>) -> Result<HashSet<String>> {
+    let graph = parse_pw_dump()?;
+    let sinks = filter_sinks(&graph, config);
+    let names = sink_names(&sinks);
+    if names == *known {
+        return Ok(names);
+    }
+    debug!("Available sinks changed: {names:?}");
+
+    if let Some(sink) = hotplug_target(config, known, &sinks, graph.default_audio_sink()) {
+        info!(
+            "Switching to sink {} after hotplug",
+            sink.info.props.node_name
+        );
+        switch_sink(sink)?;
+    }
+
+    Ok(names)
+}
+
+/// Determine the sink we should switch to, after the available sinks changed.
+///
+/// - `known` are the names of the sinks that were available before the change.
+/// - `sinks` are the currently available sinks, ordered by priority.
+/// - `current` is the name of the current default sink.
+pub fn hotplug_target<'a>(
+    config: &SinkConfig,
+    known: &HashSet<String>,
+    sinks: &'a [Node],
+    current: Option<&str>,
+) -> Option<&'a Node> {
+    let current_sink =
+        current.and_then(|name| sinks.iter().find(|sink| sink.info.props.node_name == name));
+
+    // WirePlumber replaces a removed default sink on its own, so by the time we look at the graph
+    // the removed sink usually isn't the default anymore. Hence, after any removal we make sure
+    // that the default is one of our sinks and that none of the remaining ones ranks higher.
+    let removed = known
+        .iter()
+        .any(|name| !sinks.iter().any(|sink| sink.info.props.node_name == *name));
+    if removed {
+        let best = sinks.first()?;
+        match current_sink {
+            Some(current_sink)
+                if config.order_position(current_sink) <= config.order_position(best) => {}
+            _ => return Some(best),
+        }
+    }
+
+    let current_sink = current_sink?;
+
+    let current_position = config.order_position(current_sink);
+    sinks
+        .iter()
+        .filter(|sink| !known.contains(&sink.info.props.node_name))
+        .find(|sink| config.order_position(sink) < current_position)
+}
+
+fn sink_names(sinks: &[Node]) -> HashSet<String> {
+    sinks
+        .iter()
+        .map(|sink| sink.info.props.node_name.clone())
+        .collect()
+}
@@ -0,0 +1,66 @@
+//! Machine-readable node lists for launchers such as rofi, fuzzel or dmenu.
+use clap::ValueEnum;
+use serde::Serialize;
+
+use super::{config::SinkConfig, schema::node::Node};
+
+/// The output format of node lists.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ListFormat {
+    /// Human readable multi-line blocks.
+    #[default]
+    Plain,
+    /// A JSON array of entries.
+    Json,
+    /// One entry per line, ready to be piped into dmenu-like launchers.
+    Dmenu,
+}
+
+/// A single node in a launcher menu.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MenuEntry {
+    /// The `node.name`, which stays the same across restarts.
+    pub id: String,
+    /// The alias of the node or its description.
+    pub description: String,
+    /// Whether this is the current default node.
+    pub default: bool,
+}
+
+impl MenuEntry {
+    pub fn new(node: &Node, config: &SinkConfig, default_name: Option<&str>) -> Self {
+        let props = &node.info.props;
+
+        Self {
+            id: props.node_name.clone(),
+            description: config.display_name(node).to_string(),
+            default: default_name == Some(props.node_name.as_str()),
+        }
+    }
+
+    /// A line for dmenu-like launchers, e.g. `* D10s Analog Stereo\talsa_output.usb-...`.
+    ///
+    /// The current default is marked with a `*`.
+    /// The id is separated by a tab, so launchers that support columns can hide it.
+    pub fn dmenu_line(&self) -> String {
+        let marker = if self.default { "*" } else { " " };
+        format!("{marker} {}\t{}", self.description, self.id)
+    }
+}
+
+/// Extract the id from a line that's been selected in a launcher.
+/// Plain ids are returned as they are.
+pub fn selection_id(selection: &str) -> &str {
+    selection
+        .trim_end_matches('\n')
+        .rsplit('\t')
+        .next()
+        .unwrap_or_default()
+        .trim()
+}
+
+/// Find a node by its id or by a line that's been selected in a launcher.
+pub fn find_node_by_id<'a>(nodes: &'a [Node], selection: &str) -> Option<&'a Node> {
+    let id = selection_id(selection);
+    nodes.iter().find(|node| node.info.props.node_name == id)
+}
@@ -1,5 +1,24 @@
+pub mod config;
+pub mod hotplug;
+pub mod menu;
+pub mod nodes;
+pub mod profile;
 pub mod schema;
 pub mod sink;
+pub mod source;
+pub mod stream;
+pub mod volume;
 
+pub use config::*;
+pub use hotplug::*;
+pub use menu::*;
+pub use nodes::*;
+pub use profile::*;
 pub use schema::*;
 pub use sink::*;
+pub use source::*;
+pub use stream::*;
+pub use volume::*;
+
+#[cfg(test)]
+mod tests;
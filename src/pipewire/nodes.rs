@@ -0,0 +1,228 @@
+use anyhow::{Context, Result, bail};
+use log::{debug, error, info, trace};
+use strum::Display;
+
+use super::{
+    Device,
+    schema::{graph::PwGraph, node::Node, parse_pw_dump},
+};
+use crate::{exec::Cmd, notify::*, ring::Ring};
+
+/// The kinds of audio nodes we can switch between.
+#[derive(Display, Clone, Copy, Debug, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum NodeKind {
+    /// Output devices, such as speakers and headphones.
+    Sink,
+    /// Input devices, such as microphones.
+    Source,
+}
+
+impl NodeKind {
+    /// The `media.class` of nodes of this kind.
+    pub fn media_class(&self) -> &'static str {
+        match self {
+            NodeKind::Sink => "Audio/Sink",
+            NodeKind::Source => "Audio/Source",
+        }
+    }
+
+    /// The key of the `default` metadata entry that contains the default node of this kind.
+    pub fn default_metadata_key(&self) -> &'static str {
+        match self {
+            NodeKind::Sink => "default.audio.sink",
+            NodeKind::Source => "default.audio.source",
+        }
+    }
+
+    /// The prefix of the device profiles/routes of nodes of this kind.
+    fn route_prefix(&self) -> &'static str {
+        match self {
+            NodeKind::Sink => "output",
+            NodeKind::Source => "input",
+        }
+    }
+}
+
+/// Get all audio nodes of the given kind that are currently usable.
+pub fn get_nodes(kind: NodeKind) -> Result<Vec<Node>> {
+    Ok(filter_nodes(&parse_pw_dump()?, kind))
+}
+
+/// Get all audio nodes of the given kind from the graph, no matter whether they're usable.
+pub fn nodes_of_kind(graph: &PwGraph, kind: NodeKind) -> Vec<Node> {
+    graph
+        .nodes
+        .iter()
+        .filter(|node| node.info.props.media_class == kind.media_class())
+        .cloned()
+        .collect()
+}
+
+/// Get all audio nodes of the given kind from the graph that are currently usable.
+pub fn filter_nodes(graph: &PwGraph, kind: NodeKind) -> Vec<Node> {
+    let mut valid_nodes = Vec::new();
+
+    // Run through all devices and find the one we desire.
+    for node in nodes_of_kind(graph, kind) {
+        let props = &node.info.props;
+
+        // Ignore any nodes where we can safely say that they aren't plugged in.
+        if is_not_plugged_in(&node, &graph.devices, kind) {
+            continue;
+        }
+
+        info!(
+            "Found {kind} {}: {} ({})",
+            props.object_serial, props.node_description, props.node_name
+        );
+        trace!("Raw: {node:#?}");
+
+        valid_nodes.push(node);
+    }
+
+    valid_nodes
+}
+
+/// Check whether the physical connection for a node is actually plugged in or not.
+/// To check this, we have to search the associated device for the node, go through its profiles
+/// and get the profile for that node.
+///
+/// The way we're currently doing this is by comparing the node's profile description
+/// and the actual profile description.
+/// However, this doesn't always work, as these descriptions seem to sometimes differ.
+///
+/// We handle any error graceful and return `false`. I.e. if:
+/// - No device is found
+/// - No matching profile is found
+/// - The status of the profile is `unknown`.
+fn is_not_plugged_in(node: &Node, devices: &[Device], kind: NodeKind) -> bool {
+    let device_id = &node.info.props.device_id;
+
+    // Ensure that there's a device profile description and name.
+    // Without this, we cannot check whether the node is actually plugged in.
+    //
+    // For now, this was only the case for devices like Bluetooth Headsets,
+    // which are only present if connected.
+    let Some(profile_description) = &node.info.props.device_profile_description else {
+        return false;
+    };
+    let Some(profile_name) = &node.info.props.device_profile_name else {
+        return false;
+    };
+    let prefixed_name = format!("{}:{profile_name}", kind.route_prefix());
+
+    // Get the device
+    let Some(device) = devices.iter().find(|device| device.id == *device_id) else {
+        return false;
+    };
+
+    // Go through all profiles
+    for profile in &device.info.params.profiles {
+        // There's a bit of inconsistency over here.
+        // From what I've seen, there're several possible ways of finding the matching route.
+        //
+        // - The description matches perfectly
+        // - The profile name matches the node's profile name prefixed with `output`/`input`
+        if !(&profile.description == profile_description || profile.name == prefixed_name) {
+            continue;
+        }
+
+        // If we found a matching route, check if it's not plugged in
+        return profile.available == "no";
+    }
+
+    // Check all routes.
+    // Some profile-names seem to reference routes, which is pretty confusing
+    for route in &device.info.params.routes {
+        // There's a bit of inconsistency over here.
+        // From what I've seen, there're several possible ways of finding the matching route.
+        //
+        // - The description matches perfectly
+        // - The profile name matches the node's profile name prefixed with `output`/`input`
+        if !(&route.description == profile_description || route.name == prefixed_name) {
+            continue;
+        }
+
+        // If we found a matching route, check if it's not plugged in
+        return route.available == "no";
+    }
+
+    false
+}
+
+#[derive(Display)]
+pub enum Direction {
+    Next,
+    Previous,
+}
+
+/// Get the name of the current default node of the given kind.
+pub fn get_default_node_name(kind: NodeKind) -> Result<String> {
+    let graph = parse_pw_dump()?;
+    let name = graph
+        .default_node_name(kind.default_metadata_key())
+        .context(format!("Failed to find default {kind}"))?;
+
+    Ok(name.to_owned())
+}
+
+/// Determine the node that comes before/after the current default node.
+///
+/// `nodes` should contain all nodes of the given kind, including those that cannot be used
+/// right now. That way, we know where to continue even if the current node has just been
+/// unplugged or is ignored. Nodes that aren't usable are skipped.
+/// May return None if there's no usable node.
+pub fn rotate_node<Usable>(
+    kind: NodeKind,
+    graph: &PwGraph,
+    nodes: Vec<Node>,
+    is_usable: Usable,
+    direction: Direction,
+) -> Result<Option<Node>>
+where
+    Usable: Fn(&Node) -> bool,
+{
+    // Determine the current node.
+    let current_name = graph
+        .default_node_name(kind.default_metadata_key())
+        .context(format!("Failed to find default {kind}"))?;
+    debug!("Current {kind} name: {current_name}");
+
+    // Initialize the device ring for easy iteration to the next/previous device.
+    let mut ring = Ring::new(nodes)?;
+
+    // Move the cursor to the current node.
+    // If `None` is found, return an error as we cannot determine the current node.
+    let current_device = ring.find(|(_, node)| node.info.props.node_name == current_name);
+    if current_device.is_none() {
+        error!("Could not determine current {kind}: {current_name}");
+        critical_notify(
+            1500,
+            format!("Could not determine current {kind}: {current_name}"),
+        )?;
+        bail!("Failed to determine current {kind}");
+    }
+
+    // Check if we find a node for the given name.
+    let node = match direction {
+        Direction::Next => ring.next_matching(is_usable).cloned(),
+        Direction::Previous => ring.prev_matching(is_usable).cloned(),
+    };
+
+    if let Some(ref node) = node {
+        debug!(
+            "{direction} {kind} name: {}",
+            node.info.props.node_description
+        );
+    }
+
+    Ok(node)
+}
+
+/// Set the given node as the default node of its kind.
+pub fn set_default_node(node: &Node) -> Result<()> {
+    Cmd::new(format!("wpctl set-default {}", node.info.props.object_id)).run_success()?;
+
+    Ok(())
+}
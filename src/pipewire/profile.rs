@@ -0,0 +1,179 @@
+//! List and change the profiles of audio devices.
+//!
+//! Profiles determine which nodes a device exposes.
+//! E.g. Bluetooth headsets can either use A2DP for high quality playback without a microphone
+//! or HSP/HFP with a microphone, but with terrible audio quality.
+use std::{collections::HashSet, thread::sleep, time::Duration};
+
+use anyhow::{Result, bail};
+use log::{debug, info};
+
+use super::{
+    nodes::{NodeKind, filter_nodes},
+    schema::{
+        device::{Device, Profile},
+        graph::PwGraph,
+        node::Node,
+        parse_pw_dump,
+    },
+    sink::make_default_sink,
+    source::{get_sources, make_default_source},
+};
+use crate::{exec::Cmd, notify::*};
+
+/// Nodes are recreated when the profile changes. Wait this long at most for them to show up.
+const NODE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Get all audio devices.
+pub fn get_audio_devices() -> Result<Vec<Device>> {
+    let mut devices = parse_pw_dump()?.devices;
+    devices.retain(|device| device.info.props.media_class == "Audio/Device");
+
+    Ok(devices)
+}
+
+/// Find a device by its id, or whose description or name starts with the given target.
+pub fn find_device<'a>(devices: &'a [Device], target: &str) -> Option<&'a Device> {
+    devices.iter().find(|device| {
+        let props = &device.info.props;
+        device.id.to_string() == target
+            || props.device_description.starts_with(target)
+            || props.device_name.starts_with(target)
+    })
+}
+
+/// Find a profile of a device by its index or name.
+pub fn find_profile<'a>(device: &'a Device, target: &str) -> Option<&'a Profile> {
+    device
+        .info
+        .params
+        .profiles
+        .iter()
+        .find(|profile| profile.index.to_string() == target || profile.name == target)
+}
+
+/// Activate the given profile on a device.
+pub fn set_profile(device: &Device, profile: &Profile) -> Result<()> {
+    info!(
+        "Setting profile of {} to {}",
+        device.info.props.device_description, profile.name
+    );
+    Cmd::new(format!("wpctl set-profile {} {}", device.id, profile.index)).run_success()?;
+
+    Ok(())
+}
+
+/// Whether the profile is a headset profile (HSP/HFP) with a microphone.
+pub fn is_headset_profile(profile: &Profile) -> bool {
+    profile.name.starts_with("headset-head-unit")
+}
+
+/// Whether the profile is a high fidelity playback profile (A2DP).
+pub fn is_playback_profile(profile: &Profile) -> bool {
+    profile.name.starts_with("a2dp-sink")
+}
+
+/// Determine the profile we have to switch to, to toggle the headset mode of a device.
+/// Returns `None` if the device doesn't support both modes.
+pub fn headset_toggle_profile(device: &Device) -> Option<&Profile> {
+    let in_headset_mode = device.active_profile().is_some_and(is_headset_profile);
+    let wanted = if in_headset_mode {
+        is_playback_profile
+    } else {
+        is_headset_profile
+    };
+
+    device
+        .info
+        .params
+        .profiles
+        .iter()
+        .filter(|profile| wanted(profile) && profile.available != "no")
+        .max_by_key(|profile| profile.priority)
+}
+
+/// Toggle between the playback and the headset profile of a device.
+///
+/// Afterwards, the device's sink becomes the default sink.
+/// In headset mode, its microphone also becomes the default source.
+/// When leaving headset mode, we switch to the first other microphone there is.
+pub fn toggle_headset_mode(device: &Device) -> Result<()> {
+    let description = &device.info.props.device_description;
+    let Some(profile) = headset_toggle_profile(device) else {
+        bail!("{description} doesn't support switching to headset mode");
+    };
+    let headset_mode = is_headset_profile(profile);
+
+    // The old nodes stick around for a moment after switching, so remember them.
+    let previous = device_node_ids(&parse_pw_dump()?, device);
+    set_profile(device, profile)?;
+
+    let sink = wait_for_node(device, NodeKind::Sink, &previous)?;
+    make_default_sink(&sink)?;
+
+    if headset_mode {
+        let source = wait_for_node(device, NodeKind::Source, &previous)?;
+        make_default_source(&source)?;
+    } else if let Some(source) = get_sources()?
+        .into_iter()
+        .find(|source| source.info.props.device_id != device.id)
+    {
+        make_default_source(&source)?;
+    }
+
+    // A single notification for the whole toggle, which replaces the one of the last toggle.
+    let mode = if headset_mode {
+        "Headset mode"
+    } else {
+        "Playback mode"
+    };
+    replaceable_notify(1500, format!("{description}: {mode}"), "headset-mode", None)?;
+
+    Ok(())
+}
+
+/// The ids of all nodes that currently belong to a device.
+pub fn device_node_ids(graph: &PwGraph, device: &Device) -> HashSet<usize> {
+    graph
+        .nodes
+        .iter()
+        .filter(|node| node.info.props.device_id == device.id)
+        .map(|node| node.id)
+        .collect()
+}
+
+/// Find a usable node of the given kind on a device, that isn't one of the `previous` nodes.
+pub fn new_device_node(
+    graph: &PwGraph,
+    device: &Device,
+    kind: NodeKind,
+    previous: &HashSet<usize>,
+) -> Option<Node> {
+    filter_nodes(graph, kind)
+        .into_iter()
+        .find(|node| node.info.props.device_id == device.id && !previous.contains(&node.id))
+}
+
+/// Wait for the node of the given kind to show up on a device after changing its profile.
+///
+/// Nodes in `previous` existed before the change and are ignored, as they're about to go away.
+fn wait_for_node(device: &Device, kind: NodeKind, previous: &HashSet<usize>) -> Result<Node> {
+    let interval = Duration::from_millis(250);
+    let mut waited = Duration::ZERO;
+    loop {
+        let graph = parse_pw_dump()?;
+        if let Some(node) = new_device_node(&graph, device, kind, previous) {
+            return Ok(node);
+        }
+
+        if waited >= NODE_TIMEOUT {
+            bail!(
+                "{kind} of {} didn't show up",
+                device.info.props.device_description
+            );
+        }
+        debug!("Waiting for {kind} of device {}", device.id);
+        sleep(interval);
+        waited += interval;
+    }
+}
@@ -0,0 +1,28 @@
+use serde::Deserialize;
+
+/// Representation of a Pipewire client, i.e. a connected application.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Client {
+    pub id: usize,
+    pub info: ClientInfo,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ClientInfo {
+    pub props: ClientProps,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ClientProps {
+    /// Info about the application behind this client
+    #[serde(rename = "application.name")]
+    pub application_name: Option<String>,
+    #[serde(rename = "application.process.binary")]
+    pub application_process_binary: Option<String>,
+    #[serde(rename = "application.process.id")]
+    pub application_process_id: Option<usize>,
+
+    /// The object properties of this client.
+    #[serde(rename = "object.serial")]
+    pub object_serial: Option<usize>,
+}
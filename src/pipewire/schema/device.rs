@@ -42,12 +42,30 @@ pub struct DeviceProps {
     pub client_id: usize,
 }
 
+impl Device {
+    /// The currently active profile of this device.
+    pub fn active_profile(&self) -> Option<&Profile> {
+        let active = self.info.params.active_profiles.first()?;
+
+        // The active profile only contains limited info, so look up the full profile.
+        self.info
+            .params
+            .profiles
+            .iter()
+            .find(|profile| profile.index == active.index)
+            .or(Some(active))
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Params {
     #[serde(rename = "EnumProfile", default)]
     pub profiles: Vec<Profile>,
     #[serde(rename = "EnumRoute", default)]
     pub routes: Vec<Profile>,
+    /// The currently active profile.
+    #[serde(rename = "Profile", default)]
+    pub active_profiles: Vec<Profile>,
 }
 
 /// A device can have multiple in-/outgoing routes.
@@ -62,4 +80,6 @@ pub struct Profile {
     pub description: String,
     // "yes"|"no"|"unknown"
     pub available: String,
+    #[serde(default)]
+    pub priority: usize,
 }
@@ -0,0 +1,112 @@
+use super::{
+    client::Client,
+    device::Device,
+    link::Link,
+    metadata::{Metadata, MetadataEntry},
+    node::Node,
+    port::Port,
+    stream::Stream,
+};
+
+/// All Pipewire objects from `pw-dump` we're interested in.
+#[derive(Debug, Clone, Default)]
+pub struct PwGraph {
+    pub devices: Vec<Device>,
+    /// Audio nodes that belong to a device, i.e. sinks and sources.
+    pub nodes: Vec<Node>,
+    /// Audio nodes of applications.
+    pub streams: Vec<Stream>,
+    pub ports: Vec<Port>,
+    pub links: Vec<Link>,
+    pub clients: Vec<Client>,
+    pub metadata: Vec<Metadata>,
+}
+
+impl PwGraph {
+    pub fn device(&self, id: usize) -> Option<&Device> {
+        self.devices.iter().find(|device| device.id == id)
+    }
+
+    pub fn node(&self, id: usize) -> Option<&Node> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    pub fn node_by_name(&self, name: &str) -> Option<&Node> {
+        self.nodes
+            .iter()
+            .find(|node| node.info.props.node_name == name)
+    }
+
+    pub fn stream(&self, id: usize) -> Option<&Stream> {
+        self.streams.iter().find(|stream| stream.id == id)
+    }
+
+    pub fn client(&self, id: usize) -> Option<&Client> {
+        self.clients.iter().find(|client| client.id == id)
+    }
+
+    /// All ports of the given node.
+    pub fn ports_of(&self, node_id: usize) -> impl Iterator<Item = &Port> {
+        self.ports
+            .iter()
+            .filter(move |port| port.info.props.node_id == node_id)
+    }
+
+    /// All links that carry data away from the given node.
+    pub fn links_from(&self, node_id: usize) -> impl Iterator<Item = &Link> {
+        self.links
+            .iter()
+            .filter(move |link| link.info.output_node_id == node_id)
+    }
+
+    /// All links that carry data into the given node.
+    pub fn links_to(&self, node_id: usize) -> impl Iterator<Item = &Link> {
+        self.links
+            .iter()
+            .filter(move |link| link.info.input_node_id == node_id)
+    }
+
+    /// The id of the node a stream is currently connected to.
+    ///
+    /// Playback streams are linked to their sink, recording streams are linked from their source.
+    /// There's one link per channel, but they all point to the same node.
+    pub fn stream_target(&self, stream_id: usize) -> Option<usize> {
+        self.links_from(stream_id)
+            .map(|link| link.info.input_node_id)
+            .chain(
+                self.links_to(stream_id)
+                    .map(|link| link.info.output_node_id),
+            )
+            .next()
+    }
+
+    /// Get the metadata object with the given name.
+    pub fn metadata(&self, name: &str) -> Option<&Metadata> {
+        self.metadata
+            .iter()
+            .find(|metadata| metadata.props.metadata_name == name)
+    }
+
+    /// Get a global entry of the `default` metadata, such as `default.audio.sink`.
+    pub fn default_entry(&self, key: &str) -> Option<&MetadataEntry> {
+        self.metadata("default")?
+            .metadata
+            .iter()
+            .find(|entry| entry.subject == 0 && entry.key == key)
+    }
+
+    /// Get the node name that's stored in a `default` metadata entry.
+    pub fn default_node_name(&self, key: &str) -> Option<&str> {
+        self.default_entry(key)?.value.get("name")?.as_str()
+    }
+
+    /// The name of the current default sink.
+    pub fn default_audio_sink(&self) -> Option<&str> {
+        self.default_node_name("default.audio.sink")
+    }
+
+    /// The name of the current default source.
+    pub fn default_audio_source(&self) -> Option<&str> {
+        self.default_node_name("default.audio.source")
+    }
+}
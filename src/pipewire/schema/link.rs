@@ -0,0 +1,19 @@
+use serde::Deserialize;
+
+/// Representation of a Pipewire link between the ports of two nodes.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Link {
+    pub id: usize,
+    pub info: LinkInfo,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LinkInfo {
+    /// The node that produces the data, e.g. an application's stream.
+    #[serde(rename = "output-node-id")]
+    pub output_node_id: usize,
+    /// The node that consumes the data, e.g. a sink.
+    #[serde(rename = "input-node-id")]
+    pub input_node_id: usize,
+    pub state: String,
+}
@@ -0,0 +1,31 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Representation of a Pipewire metadata object.
+///
+/// The most interesting one is the `default` metadata, which contains the default sink and source.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Metadata {
+    pub id: usize,
+    pub props: MetadataProps,
+    #[serde(default)]
+    pub metadata: Vec<MetadataEntry>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MetadataProps {
+    #[serde(rename = "metadata.name", default)]
+    pub metadata_name: String,
+}
+
+/// A single key-value entry of a metadata object.
+#[derive(Debug, Deserialize, Clone)]
+pub struct MetadataEntry {
+    /// The id of the object this entry is about. `0` for global entries.
+    pub subject: usize,
+    pub key: String,
+    #[serde(rename = "type")]
+    pub entry_type: Option<String>,
+    /// Usually a JSON object such as `{ "name": "alsa_output.pci-0000_00_1f.3.analog-stereo" }`
+    pub value: Value,
+}
@@ -1,23 +1,42 @@
 use anyhow::{Context, Result};
+pub use client::*;
 pub use device::*;
+pub use graph::*;
+pub use link::*;
+use log::warn;
+pub use metadata::*;
 pub use node::*;
+pub use port::*;
+use serde::de::DeserializeOwned;
 use serde_json::Value;
+pub use stream::*;
 
 use crate::prelude::Cmd;
 
+pub mod client;
 pub mod device;
+pub mod graph;
+pub mod link;
+pub mod metadata;
 pub mod node;
+pub mod port;
+pub mod stream;
 
-/// Parse the output of `pw_dump` and return a list of devices and nodes.
-pub fn parse_pw_dump() -> Result<(Vec<Device>, Vec<Node>)> {
-    let mut devices = Vec::new();
-    let mut nodes = Vec::new();
+/// Call `pw-dump` and return all objects we're interested in.
+pub fn parse_pw_dump() -> Result<PwGraph> {
+    let capture = Cmd::new("pw-dump").run_success()?;
+
+    parse_pw_dump_str(&capture.stdout_str())
+}
+
+/// Parse the output of `pw-dump` and return all objects we're interested in.
+pub fn parse_pw_dump_str(dump: &str) -> Result<PwGraph> {
+    let mut graph = PwGraph::default();
 
     // First off, get the raw serde json representation.
     // There're many pipewire object types in the output we aren't interested in.
     // We're going to filter out only those we want.
-    let capture = Cmd::new("pw-dump").run_success()?;
-    let objects: Vec<Value> = serde_json::from_str(&capture.stdout_str())?;
+    let objects: Vec<Value> = serde_json::from_str(dump).context("Failed to parse pw-dump")?;
 
     for object in objects {
         // The output should only contain objects. Everything else could be ignored anyway.
@@ -25,47 +44,66 @@ pub fn parse_pw_dump() -> Result<(Vec<Device>, Vec<Node>)> {
             continue;
         };
 
-        match values.get("type").unwrap() {
-            Value::String(object_type) => match object_type.as_str() {
-                "PipeWire:Interface:Node" => {
-                    // There are a few default drivers we're really not interested in.
-                    // We're only interested in those nodes that have a valid associated device.
-                    // Such nodes have a `node.info.props.device.id` property.
-                    let Some(Value::Object(infos)) = values.get("info") else {
-                        continue;
-                    };
-                    let Some(Value::Object(props)) = infos.get("props") else {
-                        continue;
-                    };
-                    let Some(Value::Number(_device_id)) = props.get("device.id") else {
-                        continue;
-                    };
-
-                    // On top of that, we must have a media_class, otherwise we cannot do anything
-                    // with it anyway.
-                    let Some(Value::String(media_class)) = props.get("media.class") else {
-                        continue;
-                    };
-                    // Furthermore, we're only interested in audio sinks.
-                    if !media_class.starts_with("Audio") {
-                        continue;
-                    }
-
-                    // We now know that there's a device id in there, so let's include that node.
-                    let node: Node = serde_json::from_value(object.clone())
-                        .context(format!("Failed to parse node: {:#?}", object.clone()))?;
-                    nodes.push(node);
+        let Some(Value::String(object_type)) = values.get("type") else {
+            continue;
+        };
+
+        match object_type.as_str() {
+            "PipeWire:Interface:Node" => {
+                // We must have a media_class, otherwise we cannot do anything with it anyway.
+                let Some(Value::String(media_class)) = object.pointer("/info/props/media.class")
+                else {
+                    continue;
+                };
+
+                // Audio streams of applications, which are played or recorded.
+                if media_class.starts_with("Stream/") && media_class.ends_with("/Audio") {
+                    graph.streams.push(parse_object(&object, "stream")?);
+                    continue;
                 }
-                "PipeWire:Interface:Device" => {
-                    let device: Device = serde_json::from_value(object.clone())
-                        .context(format!("Failed to parse device: {:#?}", object.clone()))?;
-                    devices.push(device);
+
+                // There are a few default drivers we're really not interested in.
+                // We're only interested in those nodes that have a valid associated device.
+                // Such nodes have a `node.info.props.device.id` property.
+                let Some(Value::Number(_device_id)) = object.pointer("/info/props/device.id")
+                else {
+                    continue;
+                };
+
+                // Furthermore, we're only interested in audio sinks and sources.
+                if !media_class.starts_with("Audio") {
+                    continue;
                 }
-                _ => continue,
-            },
+
+                // We now know that there's a device id in there, so let's include that node.
+                graph.nodes.push(parse_object(&object, "node")?);
+            }
+            "PipeWire:Interface:Device" => graph.devices.push(parse_object(&object, "device")?),
+            // These objects only provide additional information, so a single one that
+            // doesn't match our schema mustn't break everything else.
+            "PipeWire:Interface:Port" => push_auxiliary(&mut graph.ports, &object, "port"),
+            "PipeWire:Interface:Link" => push_auxiliary(&mut graph.links, &object, "link"),
+            "PipeWire:Interface:Client" => push_auxiliary(&mut graph.clients, &object, "client"),
+            "PipeWire:Interface:Metadata" => {
+                push_auxiliary(&mut graph.metadata, &object, "metadata")
+            }
             _ => continue,
         }
     }
 
-    Ok((devices, nodes))
+    Ok(graph)
+}
+
+/// Deserialize a single raw pipewire object.
+fn parse_object<T: DeserializeOwned>(object: &Value, name: &str) -> Result<T> {
+    serde_json::from_value(object.clone()).context(format!("Failed to parse {name}: {object:#?}"))
+}
+
+/// Deserialize an object that we can do without and skip it if it doesn't match our schema,
+/// as the output of `pw-dump` differs between pipewire versions.
+fn push_auxiliary<T: DeserializeOwned>(objects: &mut Vec<T>, object: &Value, name: &str) {
+    match parse_object(object, name) {
+        Ok(parsed) => objects.push(parsed),
+        Err(err) => warn!("Skipping {name}: {err:?}"),
+    }
 }
@@ -14,6 +14,25 @@ pub struct Node {
 pub struct NodeInfo {
     pub props: NodeProps,
     pub state: String,
+    #[serde(default)]
+    pub params: NodeParams,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct NodeParams {
+    #[serde(rename = "Props", default)]
+    pub props: Vec<NodeParamProps>,
+}
+
+/// The current properties of a node, such as its volume.
+///
+/// Nodes usually have multiple `Props` entries, not all of which contain volume info.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct NodeParamProps {
+    /// The linear volume of each channel.
+    #[serde(rename = "channelVolumes")]
+    pub channel_volumes: Option<Vec<f64>>,
+    pub mute: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
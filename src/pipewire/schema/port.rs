@@ -0,0 +1,34 @@
+use serde::Deserialize;
+
+/// Representation of a Pipewire port. Each node has one port per channel and direction.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Port {
+    pub id: usize,
+    pub info: PortInfo,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PortInfo {
+    /// "input"|"output"
+    pub direction: String,
+    pub props: PortProps,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PortProps {
+    /// The node this port belongs to
+    #[serde(rename = "node.id")]
+    pub node_id: usize,
+
+    /// Info about this very port
+    #[serde(rename = "port.name")]
+    pub port_name: String,
+    #[serde(rename = "port.alias")]
+    pub port_alias: Option<String>,
+    #[serde(rename = "audio.channel")]
+    pub audio_channel: Option<String>,
+
+    /// The object properties of this port.
+    #[serde(rename = "object.serial")]
+    pub object_serial: Option<usize>,
+}
@@ -0,0 +1,51 @@
+use serde::Deserialize;
+
+/// Representation of a Pipewire stream node, i.e. an application playing or recording audio.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Stream {
+    pub id: usize,
+    pub info: StreamInfo,
+}
+
+/// Detailed info about a stream
+#[derive(Debug, Deserialize, Clone)]
+pub struct StreamInfo {
+    pub props: StreamProps,
+    pub state: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct StreamProps {
+    /// Info about the application this stream belongs to
+    #[serde(rename = "application.name")]
+    pub application_name: Option<String>,
+    #[serde(rename = "application.process.binary")]
+    pub application_process_binary: Option<String>,
+
+    /// Info about this very node
+    #[serde(rename = "node.name")]
+    pub node_name: String,
+    #[serde(rename = "media.name")]
+    pub media_name: Option<String>,
+
+    /// The object properties of this node.
+    #[serde(rename = "object.serial")]
+    pub object_serial: usize,
+
+    /// The media info of this node
+    #[serde(rename = "media.class")]
+    pub media_class: String,
+}
+
+impl Stream {
+    /// The name of the application that owns this stream.
+    /// Falls back to the binary and node name.
+    pub fn application(&self) -> &str {
+        let props = &self.info.props;
+        props
+            .application_name
+            .as_deref()
+            .or(props.application_process_binary.as_deref())
+            .unwrap_or(&props.node_name)
+    }
+}
@@ -1,200 +1,113 @@
-use anyhow::{Context, Result, bail};
-use log::{debug, error, info, trace, warn};
-use strum::Display;
+use anyhow::Result;
+use log::warn;
 
 use super::{
-    Device,
-    schema::{node::Node, parse_pw_dump},
+    config::SinkConfig,
+    nodes::{Direction, NodeKind, filter_nodes, nodes_of_kind, rotate_node, set_default_node},
+    schema::{graph::PwGraph, node::Node, parse_pw_dump},
+    stream::{apply_routes, move_streams},
 };
-use crate::{exec::Cmd, notify::*, ring::Ring};
-
-// Some sinks are just uninteresting for me.
-const IGNORED_SINKS: &[&str] = &[
-    // Sony/Philips Digital Interface
-    // Coaxial/Optical input
-    "USB Audio S/PDIF Output",
-];
+use crate::notify::*;
 
 /// Get a map of all audio sink noes.
+/// Ignored sinks are removed and the rest is sorted according to the config file.
 pub fn get_sinks() -> Result<Vec<Node>> {
-    let (devices, nodes) = parse_pw_dump()?;
-
-    let mut valid_nodes = Vec::new();
-
-    // Run through all devices and find the one we desire.
-    for node in nodes.into_iter() {
-        let props = &node.info.props;
-        // We are only interested in devices of Audio/Sink type.
-        if &props.media_class != "Audio/Sink" {
-            continue;
-        }
-
-        // Skip all ignored sinks
-        if IGNORED_SINKS.contains(&props.node_description.as_str()) {
-            continue;
-        }
-
-        // Ignore any sinks where we can safely say that they aren't plugged in.
-        if is_not_plugged_in(&node, &devices) {
-            continue;
-        }
-
-        info!(
-            "Found device {}: {} ({})",
-            props.object_serial, props.node_description, props.node_name
-        );
-        trace!("Raw: {node:#?}");
-
-        valid_nodes.push(node);
-    }
-
-    Ok(valid_nodes)
+    Ok(filter_sinks(&parse_pw_dump()?, &SinkConfig::load()?))
 }
 
-/// Check whether the physical connection for a node is actually plugged in or not.
-/// To check this, we have to search the associated device for the node, go through its profiles
-/// and get the profile for that node.
-///
-/// The way we're currently doing this is by comparing the node's profile description
-/// and the actual profile description.
-/// However, this doesn't always work, as these descriptions seem to sometimes differ.
-///
-/// We handle any error graceful and return `false`. I.e. if:
-/// - No device is found
-/// - No matching profile is found
-/// - The status of the profile is `unknown`.
-fn is_not_plugged_in(node: &Node, devices: &[Device]) -> bool {
-    let device_id = &node.info.props.device_id;
-
-    // Ensure that there's a device profile description and name.
-    // Without this, we cannot check whether the node is actually plugged in.
-    //
-    // For now, this was only the case for devices like Bluetooth Headsets,
-    // which are only present if connected.
-    let Some(profile_description) = &node.info.props.device_profile_description else {
-        return false;
-    };
-    let Some(profile_name) = &node.info.props.device_profile_name else {
-        return false;
-    };
-
-    // Get the device
-    let Some(device) = devices.iter().find(|device| device.id == *device_id) else {
-        return false;
-    };
-
-    // Go through all profiles
-    for profile in &device.info.params.profiles {
-        // There's a bit of inconsistency over here.
-        // From what I've seen, there're several possible ways of finding the matching route.
-        //
-        // - The description matches perfectly
-        // - The profile name matches the node's profile name prefixed with `output`
-        if !(&profile.description == profile_description
-            || profile.name == format!("output:{profile_name}"))
-        {
-            continue;
-        }
-
-        // If we found a matching route, check if it's not plugged in
-        return profile.available == "no";
-    }
+/// Get all usable audio sink nodes from the graph.
+pub fn filter_sinks(graph: &PwGraph, config: &SinkConfig) -> Vec<Node> {
+    let mut nodes = filter_nodes(graph, NodeKind::Sink);
 
-    // Check all routes.
-    // Some profile-names seem to reference routes, which is pretty confusing
-    for route in &device.info.params.routes {
-        // There's a bit of inconsistency over here.
-        // From what I've seen, there're several possible ways of finding the matching route.
-        //
-        // - The description matches perfectly
-        // - The profile name matches the node's profile name prefixed with `output`
-        if !(&route.description == profile_description
-            || route.name == format!("output:{profile_name}"))
-        {
-            continue;
-        }
-
-        // If we found a matching route, check if it's not plugged in
-        return route.available == "no";
-    }
+    // Skip all ignored sinks
+    nodes.retain(|node| !config.is_ignored(node));
+
+    // The sort is stable, so unmatched sinks keep the order in which pipewire reports them.
+    nodes.sort_by_key(|node| config.order_position(node));
 
-    false
+    nodes
 }
 
-#[derive(Display)]
-pub enum Direction {
-    Next,
-    Previous,
+/// Find the sink with the given alias or whose description starts with the given target.
+pub fn find_sink<'a>(config: &SinkConfig, sinks: &'a [Node], target: &str) -> Option<&'a Node> {
+    sinks
+        .iter()
+        .find(|sink| {
+            config
+                .alias(sink)
+                .is_some_and(|alias| alias.eq_ignore_ascii_case(target))
+        })
+        .or_else(|| {
+            sinks
+                .iter()
+                .find(|sink| sink.info.props.node_description.starts_with(target))
+        })
+}
+
+/// Find the built-in sink of the machine, as configured by [SinkConfig::built_in].
+pub fn find_built_in_sink<'a>(config: &SinkConfig, sinks: &'a [Node]) -> Option<&'a Node> {
+    sinks.iter().find(|sink| config.built_in.matches(sink))
 }
 
 /// Try to determine the id and description of the targeted sink.
 /// May return None if the target sink cannot be found.
 pub fn rotate_sink(direction: Direction) -> Result<Option<Node>> {
-    // Determine the current sink.
-    let output = Cmd::new("pactl get-default-sink")
-        .run_success()
-        .context("Failed to find default sink")?;
-    let current_sink_name = output.stdout_str().trim().to_owned();
-    debug!("Current sink name: {current_sink_name}");
-
-    // Initialize the device ring for easy iteration to the next/previous device.
-    let nodes = get_sinks()?;
-    let mut ring = Ring::new(nodes.clone())?;
-
-    // Move the cursor to the current node.
-    // If `None` is found, return an error as we cannot determine the current sink.
-    let current_device = ring.find(|(_, node)| node.info.props.node_name == current_sink_name);
-    if current_device.is_none() {
-        error!("Could not determine current sink: {current_sink_name}");
-        critical_notify(
-            1500,
-            format!("Could not determine current sink: {current_sink_name}"),
-        )?;
-        bail!("Failed to determine current sink");
-    }
+    rotate_sink_in(&parse_pw_dump()?, &SinkConfig::load()?, direction)
+}
 
-    // Check if we find a node for the given name.
-    let node = match direction {
-        Direction::Next => Some((ring.next()).clone()),
-        Direction::Previous => Some((ring.prev()).clone()),
-    };
-
-    if let Some(ref node) = node {
-        debug!(
-            "{direction} sink name: {}",
-            node.info.props.node_description
-        );
-    }
+/// Determine the sink that comes before/after the current default sink in the graph.
+/// Unusable and ignored sinks are skipped.
+pub fn rotate_sink_in(
+    graph: &PwGraph,
+    config: &SinkConfig,
+    direction: Direction,
+) -> Result<Option<Node>> {
+    let usable = filter_sinks(graph, config);
+
+    // Use the same order for all sinks, so the position of unusable sinks is stable as well.
+    let mut sinks = nodes_of_kind(graph, NodeKind::Sink);
+    sinks.sort_by_key(|node| config.order_position(node));
+
+    rotate_node(
+        NodeKind::Sink,
+        graph,
+        sinks,
+        |node| usable.iter().any(|sink| sink.id == node.id),
+        direction,
+    )
+}
+
+/// Set the target device as the default sink.
+/// Also take all inputs that're currently open and move them over to the target device.
+/// This allows for a clean transition of any active streams when switching devices.
+pub fn switch_sink(node: &Node) -> Result<()> {
+    make_default_sink(node)?;
+
+    // Inform the user about the sink we just switched to.
+    let config = SinkConfig::load()?;
+    notify(
+        1500,
+        format!("Changed sink to {}", config.display_name(node)),
+    )?;
 
-    Ok(node.clone())
+    Ok(())
 }
 
-/// Search all inputs and switch them over to the given device.
-pub fn move_inputs_to_sink(node_object_serial: usize) -> Result<()> {
-    // Get all currently active sink inputs.
-    // Output format looks like this:
-    //
-    // 188 56 187 PipeWire float32le 2ch 48000Hz
-    //
-    // We're interested in the first number.
-    let capture = Cmd::new("pactl list short sink-inputs").run_success()?;
-
-    let input_ids: Vec<String> = capture
-        .stdout_str()
-        .split('\n')
-        .filter(|line| !line.trim().is_empty())
-        .filter_map(|line| line.split('\t').next().map(|id| id.to_string()))
-        .collect();
-
-    debug!("Input Ids: {input_ids:?}");
-
-    for id in input_ids {
-        let result =
-            Cmd::new(format!("pactl move-sink-input {id} {node_object_serial}")).run_success();
-        if let Err(err) = result {
-            warn!("Failed to switch input {id} to new sink: {err:?}");
-        };
+/// Like [switch_sink], but without notifying the user.
+pub fn make_default_sink(node: &Node) -> Result<()> {
+    // Set the default sink.
+    set_default_node(node)?;
+
+    move_inputs_to_sink(node.info.props.object_serial)?;
+    // Applications with a routing rule stay on their configured sink.
+    if let Err(err) = apply_routes() {
+        warn!("Failed to apply routing rules: {err:?}");
     }
 
     Ok(())
 }
+
+/// Search all inputs and switch them over to the given device.
+pub fn move_inputs_to_sink(node_object_serial: usize) -> Result<()> {
+    move_streams("Stream/Output/Audio", node_object_serial)
+}
@@ -0,0 +1,60 @@
+use anyhow::Result;
+
+use super::{
+    nodes::{Direction, NodeKind, filter_nodes, nodes_of_kind, rotate_node, set_default_node},
+    schema::{graph::PwGraph, node::Node, parse_pw_dump},
+    stream::move_streams,
+};
+use crate::notify::*;
+
+/// Get all audio source nodes, i.e. microphones.
+pub fn get_sources() -> Result<Vec<Node>> {
+    Ok(filter_sources(&parse_pw_dump()?))
+}
+
+/// Get all usable audio source nodes from the graph.
+pub fn filter_sources(graph: &PwGraph) -> Vec<Node> {
+    filter_nodes(graph, NodeKind::Source)
+}
+
+/// Try to determine the id and description of the targeted source.
+/// May return None if the target source cannot be found.
+pub fn rotate_source(direction: Direction) -> Result<Option<Node>> {
+    let graph = parse_pw_dump()?;
+    let usable = filter_sources(&graph);
+
+    rotate_node(
+        NodeKind::Source,
+        &graph,
+        nodes_of_kind(&graph, NodeKind::Source),
+        |node| usable.iter().any(|source| source.id == node.id),
+        direction,
+    )
+}
+
+/// Set the target device as the default source.
+/// Also move all applications that're currently recording over to the target device.
+pub fn switch_source(node: &Node) -> Result<()> {
+    make_default_source(node)?;
+
+    // Inform the user about the source we just switched to.
+    notify(
+        1500,
+        format!("Changed source to {}", node.info.props.node_description),
+    )?;
+
+    Ok(())
+}
+
+/// Like [switch_source], but without notifying the user.
+pub fn make_default_source(node: &Node) -> Result<()> {
+    // Set the default source.
+    set_default_node(node)?;
+
+    move_outputs_to_source(node.info.props.object_serial)
+}
+
+/// Search all source outputs (recording streams) and switch them over to the given device.
+pub fn move_outputs_to_source(node_object_serial: usize) -> Result<()> {
+    move_streams("Stream/Input/Audio", node_object_serial)
+}
@@ -0,0 +1,136 @@
+use anyhow::Result;
+use log::{debug, info, warn};
+
+use super::{
+    config::SinkConfig,
+    schema::{node::Node, parse_pw_dump, stream::Stream},
+    sink::{find_sink, get_sinks},
+};
+use crate::exec::Cmd;
+
+/// An application's stream that plays audio.
+#[derive(Debug, Clone)]
+pub struct OutputStream {
+    pub stream: Stream,
+    /// The id of the node this stream is currently connected to.
+    pub target: Option<usize>,
+}
+
+/// Get all streams of applications that're playing audio and the sink they're connected to.
+pub fn get_output_streams() -> Result<Vec<OutputStream>> {
+    let graph = parse_pw_dump()?;
+
+    let output_streams = graph
+        .streams
+        .iter()
+        .filter(|stream| stream.info.props.media_class == "Stream/Output/Audio")
+        .map(|stream| OutputStream {
+            stream: stream.clone(),
+            target: graph.stream_target(stream.id),
+        })
+        .collect();
+
+    Ok(output_streams)
+}
+
+/// Check whether a stream belongs to the given application.
+/// Both the application's name and binary are matched case-insensitively.
+pub fn stream_matches_application(stream: &Stream, application: &str) -> bool {
+    let application = application.to_lowercase();
+    let props = &stream.info.props;
+
+    [&props.application_name, &props.application_process_binary]
+        .into_iter()
+        .flatten()
+        .any(|name| name.to_lowercase().contains(&application))
+}
+
+/// Move a single stream over to the given sink.
+pub fn move_stream_to_sink(stream: &Stream, sink: &Node) -> Result<()> {
+    debug!(
+        "Moving stream {} of {} to {}",
+        stream.id,
+        stream.application(),
+        sink.info.props.node_description
+    );
+
+    set_stream_target(stream.id, sink.info.props.object_serial)
+}
+
+/// Tell the session manager to connect a stream to the node with the given serial.
+/// This is the same thing `pactl move-sink-input` and `pactl move-source-output` do.
+pub fn set_stream_target(stream_id: usize, node_object_serial: usize) -> Result<()> {
+    Cmd::new(format!(
+        "pw-metadata {stream_id} target.object {node_object_serial} Spa:Id"
+    ))
+    .run_success()?;
+
+    Ok(())
+}
+
+/// Move all streams of the given media class over to the node with the given serial.
+pub fn move_streams(media_class: &str, node_object_serial: usize) -> Result<()> {
+    let graph = parse_pw_dump()?;
+
+    let stream_ids: Vec<usize> = graph
+        .streams
+        .iter()
+        .filter(|stream| stream.info.props.media_class == media_class)
+        .map(|stream| stream.id)
+        .collect();
+
+    debug!("{media_class} stream ids: {stream_ids:?}");
+
+    for id in stream_ids {
+        if let Err(err) = set_stream_target(id, node_object_serial) {
+            warn!("Failed to move stream {id} to {node_object_serial}: {err:?}");
+        };
+    }
+
+    Ok(())
+}
+
+/// Move all streams of the given application over to the given sink.
+/// Returns the amount of moved streams.
+pub fn move_application_to_sink(application: &str, sink: &Node) -> Result<usize> {
+    let mut moved = 0;
+    for output_stream in get_output_streams()? {
+        if !stream_matches_application(&output_stream.stream, application) {
+            continue;
+        }
+
+        move_stream_to_sink(&output_stream.stream, sink)?;
+        moved += 1;
+    }
+
+    Ok(moved)
+}
+
+/// Apply the routing rules from the config file.
+/// Rules for sinks that aren't available right now are skipped.
+pub fn apply_routes() -> Result<()> {
+    let config = SinkConfig::load()?;
+    if config.routes.is_empty() {
+        return Ok(());
+    }
+
+    let sinks = get_sinks()?;
+    for rule in &config.routes {
+        let Some(sink) = find_sink(&config, &sinks, &rule.sink) else {
+            info!(
+                "Sink {} for {} isn't available",
+                rule.sink, rule.application
+            );
+            continue;
+        };
+
+        if let Err(err) = move_application_to_sink(&rule.application, sink) {
+            warn!(
+                "Failed to route {} to {}: {err:?}",
+                rule.application, rule.sink
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,367 @@
+//! Tests against captured (and trimmed down) `pw-dump` outputs of various setups.
+use std::collections::HashSet;
+
+use anyhow::Result;
+use rstest::rstest;
+
+use super::{
+    config::SinkConfig,
+    hotplug::hotplug_target,
+    menu::{MenuEntry, find_node_by_id},
+    nodes::{Direction, NodeKind},
+    profile::{device_node_ids, find_profile, headset_toggle_profile, new_device_node},
+    schema::{graph::PwGraph, node::Node, parse_pw_dump_str},
+    sink::{filter_sinks, find_built_in_sink, find_sink, rotate_sink_in},
+    source::filter_sources,
+    volume::{Volume, get_volume},
+};
+
+/// Parse one of the `pw-dump` fixtures in the `fixtures` directory.
+fn fixture(name: &str) -> Result<PwGraph> {
+    let dump = match name {
+        "usb_dac" => include_str!("fixtures/usb_dac.json"),
+        "hdmi" => include_str!("fixtures/hdmi.json"),
+        "bluetooth" => include_str!("fixtures/bluetooth.json"),
+        "spdif" => include_str!("fixtures/spdif.json"),
+        "unplugged_headphones" => include_str!("fixtures/unplugged_headphones.json"),
+        _ => panic!("Unknown fixture: {name}"),
+    };
+
+    parse_pw_dump_str(dump)
+}
+
+fn descriptions(nodes: &[Node]) -> Vec<&str> {
+    nodes
+        .iter()
+        .map(|node| node.info.props.node_description.as_str())
+        .collect()
+}
+
+#[rstest]
+#[case::usb_dac("usb_dac", &["D10s Analog Stereo"])]
+// The second HDMI port has nothing connected to it.
+#[case::hdmi(
+    "hdmi",
+    &["GA102 High Definition Audio Controller Digital Stereo (HDMI)"]
+)]
+// Bluetooth nodes don't have any profile info, but they only exist while connected.
+#[case::bluetooth("bluetooth", &["WH-1000XM4"])]
+// S/PDIF is on the ignore list.
+#[case::spdif("spdif", &["USB Audio Line Out"])]
+// The headphone jack's route is marked as unavailable.
+#[case::unplugged_headphones("unplugged_headphones", &["Built-in Audio Speaker"])]
+fn usable_sinks(#[case] name: &str, #[case] expected: &[&str]) -> Result<()> {
+    let graph = fixture(name)?;
+    assert_eq!(
+        descriptions(&filter_sinks(&graph, &SinkConfig::default())),
+        expected
+    );
+
+    Ok(())
+}
+
+#[test]
+fn sink_preferences() -> Result<()> {
+    let graph = fixture("spdif")?;
+    let config: SinkConfig = toml::from_str(
+        r#"
+        ignored = [{ api = "bluez5" }]
+        order = [{ description = "S/PDIF" }, { name = "unknown" }]
+
+        [[aliases]]
+        alias = "optical"
+        match = { description = "S/PDIF", api = "alsa" }
+        "#,
+    )?;
+
+    // Configuring ignored sinks replaces the default, so S/PDIF is available now and comes first.
+    let sinks = filter_sinks(&graph, &config);
+    assert_eq!(
+        descriptions(&sinks),
+        ["USB Audio S/PDIF Output", "USB Audio Line Out"]
+    );
+
+    let sink = find_sink(&config, &sinks, "Optical").unwrap();
+    assert_eq!(config.display_name(sink), "optical");
+    let sink = find_sink(&config, &sinks, "USB Audio Line").unwrap();
+    assert_eq!(config.display_name(sink), "USB Audio Line Out");
+
+    // Invalid regexes are rejected when loading the config.
+    assert!(toml::from_str::<SinkConfig>(r#"order = [{ description = "(" }]"#).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn built_in_sink() -> Result<()> {
+    let mut graph = fixture("unplugged_headphones")?;
+    graph.nodes.extend(fixture("bluetooth")?.nodes);
+    let sinks = filter_sinks(&graph, &SinkConfig::default());
+
+    let sink = find_built_in_sink(&SinkConfig::default(), &sinks).unwrap();
+    assert_eq!(sink.info.props.node_description, "Built-in Audio Speaker");
+
+    let config: SinkConfig = toml::from_str(r#"built_in = { api = "bluez5" }"#)?;
+    let sink = find_built_in_sink(&config, &sinks).unwrap();
+    assert_eq!(sink.info.props.node_description, "WH-1000XM4");
+
+    Ok(())
+}
+
+#[rstest]
+#[case::usb_dac("usb_dac", &[])]
+#[case::bluetooth("bluetooth", &["WH-1000XM4"])]
+#[case::unplugged_headphones(
+    "unplugged_headphones",
+    &["Built-in Audio Digital Microphone"]
+)]
+fn usable_sources(#[case] name: &str, #[case] expected: &[&str]) -> Result<()> {
+    let graph = fixture(name)?;
+    assert_eq!(descriptions(&filter_sources(&graph)), expected);
+
+    Ok(())
+}
+
+#[test]
+fn graph_of_usb_dac() -> Result<()> {
+    let graph = fixture("usb_dac")?;
+
+    // Only actual devices end up as nodes. Drivers and streams are sorted out.
+    assert_eq!(graph.nodes.len(), 1);
+    assert_eq!(graph.streams.len(), 1);
+    assert_eq!(
+        graph.default_audio_sink(),
+        Some("alsa_output.usb-Topping_D10s-00.analog-stereo")
+    );
+    assert_eq!(graph.default_audio_source(), None);
+
+    let stream = &graph.streams[0];
+    assert_eq!(stream.application(), "Firefox");
+    assert_eq!(graph.stream_target(stream.id), Some(graph.nodes[0].id));
+
+    assert_eq!(
+        get_volume(&graph.nodes[0]),
+        Some(Volume {
+            percent: 50.0,
+            muted: false,
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn invalid_dump() {
+    assert!(parse_pw_dump_str("not json").is_err());
+}
+
+#[test]
+fn skips_broken_auxiliary_objects() -> Result<()> {
+    let dump = include_str!("fixtures/spdif.json")
+        .trim_end()
+        .trim_end_matches(']');
+    let dump = format!(
+        r#"{dump},
+        {{ "id": 900, "type": "PipeWire:Interface:Link", "info": "unexpected" }},
+        {{ "id": 901, "info": {{}} }}
+        ]"#
+    );
+
+    let graph = parse_pw_dump_str(&dump)?;
+    assert_eq!(graph.nodes.len(), fixture("spdif")?.nodes.len());
+    assert!(graph.links.iter().all(|link| link.id != 900));
+
+    Ok(())
+}
+
+#[test]
+fn hotplug_switches_by_priority() -> Result<()> {
+    let config: SinkConfig = toml::from_str(r#"order = [{ api = "bluez5" }]"#)?;
+    let builtin = fixture("unplugged_headphones")?;
+    let mut connected = fixture("unplugged_headphones")?;
+    let bluetooth = fixture("bluetooth")?;
+    connected.devices.extend(bluetooth.devices.clone());
+    connected.nodes.extend(bluetooth.nodes.clone());
+
+    let speaker = builtin.default_audio_sink().unwrap();
+    let headset = bluetooth.default_audio_sink().unwrap();
+    let before = filter_sinks(&builtin, &config);
+    let after = filter_sinks(&connected, &config);
+    let names = |sinks: &[Node]| -> HashSet<String> {
+        sinks
+            .iter()
+            .map(|sink| sink.info.props.node_name.clone())
+            .collect()
+    };
+
+    // The headset has a higher priority, so we switch once it's connected.
+    let target = hotplug_target(&config, &names(&before), &after, Some(speaker));
+    assert_eq!(target.unwrap().info.props.node_name, headset);
+
+    // Nothing happens if the headset already is the default.
+    let target = hotplug_target(&config, &names(&before), &after, Some(headset));
+    assert!(target.is_none());
+
+    // Without any configured priority, new sinks don't take over.
+    let default = SinkConfig::default();
+    let target = hotplug_target(&default, &names(&before), &after, Some(speaker));
+    assert!(target.is_none());
+
+    // Fall back to the speaker once the headset disappears.
+    let target = hotplug_target(&config, &names(&after), &before, Some(headset));
+    assert_eq!(target.unwrap().info.props.node_name, speaker);
+
+    Ok(())
+}
+
+#[test]
+fn hotplug_overrides_wireplumber_fallback() -> Result<()> {
+    let config: SinkConfig = toml::from_str(
+        r#"
+        ignored = [{ description = "HDMI 2" }]
+        order = [{ api = "bluez5" }, { description = "Line Out" }]
+        "#,
+    )?;
+    let mut before = fixture("spdif")?;
+    for other in [fixture("hdmi")?, fixture("bluetooth")?] {
+        before.devices.extend(other.devices);
+        before.nodes.extend(other.nodes);
+    }
+    let mut after = before.clone();
+    after
+        .nodes
+        .retain(|node| !node.info.props.node_name.starts_with("bluez"));
+
+    let known: HashSet<String> = filter_sinks(&before, &config)
+        .into_iter()
+        .map(|sink| sink.info.props.node_name)
+        .collect();
+    let sinks = filter_sinks(&after, &config);
+    let name = |description: &str| -> String {
+        after
+            .nodes
+            .iter()
+            .find(|node| node.info.props.node_description.ends_with(description))
+            .map(|node| node.info.props.node_name.clone())
+            .unwrap()
+    };
+    let line_out = name("Line Out");
+
+    // WirePlumber already replaced the headset with a sink that ranks lower.
+    let target = hotplug_target(&config, &known, &sinks, Some(&name("S/PDIF Output")));
+    assert_eq!(target.unwrap().info.props.node_name, line_out);
+
+    // Or with a sink that's ignored.
+    let target = hotplug_target(&config, &known, &sinks, Some(&name("(HDMI 2)")));
+    assert_eq!(target.unwrap().info.props.node_name, line_out);
+
+    // Its choice is kept if it already is the best remaining sink.
+    assert!(hotplug_target(&config, &known, &sinks, Some(&line_out)).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn headset_mode_toggle() -> Result<()> {
+    let mut graph = fixture("bluetooth")?;
+    let device = &mut graph.devices[0];
+    assert_eq!(device.active_profile().unwrap().name, "a2dp-sink");
+
+    // Switch to headset mode while playing via A2DP.
+    let profile = headset_toggle_profile(device).unwrap();
+    assert_eq!(profile.name, "headset-head-unit");
+
+    // And back to the A2DP profile with the highest priority.
+    let headset = find_profile(device, "headset-head-unit").unwrap().clone();
+    device.info.params.active_profiles = vec![headset];
+    let profile = headset_toggle_profile(device).unwrap();
+    assert_eq!(profile.name, "a2dp-sink");
+
+    // Other devices don't have a headset mode.
+    let graph = fixture("hdmi")?;
+    assert!(headset_toggle_profile(&graph.devices[0]).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn waits_for_new_nodes_after_profile_change() -> Result<()> {
+    let mut graph = fixture("bluetooth")?;
+    let device = graph.devices[0].clone();
+    let previous = device_node_ids(&graph, &device);
+    assert!(!previous.is_empty());
+
+    // The old sink is still around right after the switch and must not be picked.
+    assert!(new_device_node(&graph, &device, NodeKind::Sink, &previous).is_none());
+
+    // Then the new sink shows up.
+    let mut sink = new_device_node(&graph, &device, NodeKind::Sink, &HashSet::new()).unwrap();
+    sink.id = 999;
+    graph.nodes.push(sink);
+    let node = new_device_node(&graph, &device, NodeKind::Sink, &previous).unwrap();
+    assert_eq!(node.id, 999);
+
+    Ok(())
+}
+
+#[test]
+fn menu_entries_round_trip() -> Result<()> {
+    let graph = fixture("spdif")?;
+    let config: SinkConfig = toml::from_str(
+        r#"
+        ignored = []
+        aliases = [{ alias = "optical", match = { description = "S/PDIF" } }]
+        "#,
+    )?;
+    let sinks = filter_sinks(&graph, &config);
+    let entries: Vec<MenuEntry> = sinks
+        .iter()
+        .map(|sink| MenuEntry::new(sink, &config, graph.default_audio_sink()))
+        .collect();
+
+    let lines: Vec<String> = entries.iter().map(MenuEntry::dmenu_line).collect();
+    assert_eq!(
+        lines,
+        [
+            "* USB Audio Line Out\talsa_output.usb-Generic_USB_Audio-00.HiFi__hw_Audio_1__sink",
+            "  optical\talsa_output.usb-Generic_USB_Audio-00.HiFi__hw_Audio_3__sink",
+        ]
+    );
+
+    // Both the selected line and the plain id can be used to find the sink again.
+    let selected = find_node_by_id(&sinks, &format!("{}\n", lines[1])).unwrap();
+    assert_eq!(selected.id, sinks[1].id);
+    let selected = find_node_by_id(&sinks, &entries[0].id).unwrap();
+    assert_eq!(selected.id, sinks[0].id);
+    assert!(find_node_by_id(&sinks, "unknown").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn rotation_skips_unusable_sinks() -> Result<()> {
+    let config = SinkConfig::default();
+    let mut graph = fixture("unplugged_headphones")?;
+    let bluetooth = fixture("bluetooth")?;
+    graph.devices.extend(bluetooth.devices.clone());
+    graph.nodes.extend(bluetooth.nodes.clone());
+
+    let speaker = graph.default_audio_sink().unwrap().to_string();
+    let headset = bluetooth.default_audio_sink().unwrap();
+    let rotate = |graph: &PwGraph, direction| -> Result<String> {
+        let sink = rotate_sink_in(graph, &config, direction)?.unwrap();
+        Ok(sink.info.props.node_name)
+    };
+
+    // The unplugged headphones between the speaker and the headset are skipped.
+    assert_eq!(rotate(&graph, Direction::Next)?, headset);
+    assert_eq!(rotate(&graph, Direction::Previous)?, headset);
+
+    // Rotating also works if the current default sink isn't usable anymore.
+    let headphones = graph.nodes[1].info.props.node_name.clone();
+    graph.metadata[0].metadata[0].value = serde_json::json!({ "name": headphones });
+    assert_eq!(rotate(&graph, Direction::Next)?, headset);
+    assert_eq!(rotate(&graph, Direction::Previous)?, speaker);
+
+    Ok(())
+}
@@ -0,0 +1,133 @@
+//! Volume and mute control for pipewire nodes.
+//!
+//! Volumes are handled in percent on a cubic scale, just like `wpctl` and most mixers do.
+//! I.e. `50%` corresponds to a linear channel volume of `0.125`.
+use anyhow::{Context, Result, anyhow};
+use log::debug;
+
+use super::{
+    nodes::{NodeKind, get_default_node_name, get_nodes},
+    schema::node::Node,
+};
+use crate::{exec::Cmd, notify::replaceable_notify};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Volume {
+    /// The volume in percent.
+    pub percent: f64,
+    pub muted: bool,
+}
+
+/// Get the current default node of the given kind.
+pub fn get_default_node(kind: NodeKind) -> Result<Node> {
+    let name = get_default_node_name(kind)?;
+
+    get_nodes(kind)?
+        .into_iter()
+        .find(|node| node.info.props.node_name == name)
+        .ok_or(anyhow!("Couldn't find default {kind} {name}"))
+}
+
+/// Get a node of the given kind by its object id.
+pub fn get_node_by_id(kind: NodeKind, id: usize) -> Result<Node> {
+    get_nodes(kind)?
+        .into_iter()
+        .find(|node| node.id == id)
+        .ok_or(anyhow!("Couldn't find {kind} with id {id}"))
+}
+
+/// Read the current volume of a node from its `Props` params.
+/// Returns `None` if the node doesn't expose any volume info.
+pub fn get_volume(node: &Node) -> Option<Volume> {
+    let props = node
+        .info
+        .params
+        .props
+        .iter()
+        .find(|props| props.channel_volumes.is_some())?;
+    let channel_volumes = props.channel_volumes.as_ref()?;
+
+    Some(Volume {
+        percent: percent_from_channel_volumes(channel_volumes)?,
+        muted: props.mute.unwrap_or_default(),
+    })
+}
+
+/// Convert linear channel volumes to a single cubic volume in percent.
+fn percent_from_channel_volumes(channel_volumes: &[f64]) -> Option<f64> {
+    if channel_volumes.is_empty() {
+        return None;
+    }
+    let average = channel_volumes.iter().sum::<f64>() / channel_volumes.len() as f64;
+
+    // Round to get rid of floating point noise, such as `49.99999`.
+    Some((average.cbrt() * 100.0 * 100.0).round() / 100.0)
+}
+
+/// Set the volume of a node in percent.
+/// The volume is clamped to `0..=max_percent`.
+pub fn set_volume(node: &Node, percent: f64, max_percent: f64) -> Result<f64> {
+    let percent = percent.clamp(0.0, max_percent);
+    debug!(
+        "Setting volume of {} to {percent}%",
+        node.info.props.node_description
+    );
+
+    Cmd::new(format!("wpctl set-volume {} {}", node.id, percent / 100.0))
+        .run_success()
+        .context("Failed to set volume")?;
+
+    Ok(percent)
+}
+
+/// Change the volume of a node by the given amount of percent.
+/// The new volume is clamped to `0..=max_percent`.
+pub fn step_volume(node: &Node, step: f64, max_percent: f64) -> Result<Volume> {
+    let mut volume = get_volume(node).ok_or(anyhow!(
+        "Couldn't determine the volume of {}",
+        node.info.props.node_description
+    ))?;
+
+    volume.percent = set_volume(node, volume.percent + step, max_percent)?;
+
+    Ok(volume)
+}
+
+/// Toggle the mute state of a node and return the new state.
+pub fn toggle_mute(node: &Node) -> Result<bool> {
+    let muted = get_volume(node).is_some_and(|volume| volume.muted);
+
+    Cmd::new(format!("wpctl set-mute {} toggle", node.id))
+        .run_success()
+        .context("Failed to toggle mute")?;
+
+    Ok(!muted)
+}
+
+/// Show the volume of a node in a notification that's updated in place.
+pub fn notify_volume(node: &Node, volume: Volume) -> Result<()> {
+    let description = &node.info.props.node_description;
+    let percent = volume.percent.round() as usize;
+
+    let message = if volume.muted {
+        format!("{description}: muted")
+    } else {
+        format!("{description}: {percent}%")
+    };
+
+    replaceable_notify(1500, message, "volume", Some(percent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_channel_volumes() {
+        assert_eq!(percent_from_channel_volumes(&[]), None);
+        assert_eq!(percent_from_channel_volumes(&[1.0, 1.0]), Some(100.0));
+        assert_eq!(percent_from_channel_volumes(&[0.125, 0.125]), Some(50.0));
+        // Channels are averaged before converting them.
+        assert_eq!(percent_from_channel_volumes(&[0.0, 0.25]), Some(50.0));
+    }
+}
@@ -0,0 +1,221 @@
+//! A minimal client for the netlink process connector.
+//!
+//! Once subscribed, the kernel sends us an event for every `exec` and `exit` on the system.
+//! Subscribing requires `CAP_NET_ADMIN` in the initial namespace, so callers need a fallback.
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, bail};
+use log::trace;
+
+use crate::netlink::{NLMSG_HEADER_LEN, NetlinkSocket, parse_messages, read_u32};
+
+/// The multicast group and value of the process connector (`linux/connector.h`).
+const CN_IDX_PROC: u32 = 1;
+const CN_VAL_PROC: u32 = 1;
+
+/// Operations to (un-)subscribe from process events (`linux/cn_proc.h`).
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+const PROC_CN_MCAST_IGNORE: u32 = 2;
+
+/// The event types we're interested in (`linux/cn_proc.h`).
+const PROC_EVENT_NONE: u32 = 0;
+const PROC_EVENT_EXEC: u32 = 0x0000_0002;
+const PROC_EVENT_EXIT: u32 = 0x8000_0000;
+
+/// Size of the `cn_msg` header.
+const CN_MSG_HEADER_LEN: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectorEvent {
+    /// A process replaced its image via `exec`.
+    Exec { pid: i32 },
+    /// A process (not just one of its threads) exited.
+    Exit { pid: i32 },
+    /// The socket buffer overflowed and events have been lost.
+    Overrun,
+}
+
+pub struct ProcConnector {
+    socket: NetlinkSocket,
+}
+
+impl ProcConnector {
+    /// Open a netlink connector socket and subscribe to process events.
+    ///
+    /// This fails if we aren't allowed to subscribe.
+    pub fn new() -> Result<Self> {
+        let socket = NetlinkSocket::new(libc::SOCK_DGRAM, libc::NETLINK_CONNECTOR, CN_IDX_PROC)?;
+
+        let connector = Self { socket };
+        connector.send_operation(PROC_CN_MCAST_LISTEN)?;
+        connector.wait_for_ack(Duration::from_secs(1))?;
+
+        Ok(connector)
+    }
+
+    /// Wait up to `timeout` for events and return all events that're available.
+    pub fn receive(&self, timeout: Duration) -> Result<Vec<ConnectorEvent>> {
+        let mut events = Vec::new();
+        if !self.socket.poll(timeout)? {
+            return Ok(events);
+        }
+
+        // Drain the socket, there're usually lots of events at once.
+        let mut buffer = [0u8; 8192];
+        loop {
+            let len = match self.socket.read(&mut buffer) {
+                Ok(Some(len)) => len,
+                Ok(None) => break,
+                Err(err) if err.raw_os_error() == Some(libc::ENOBUFS) => {
+                    events.push(ConnectorEvent::Overrun);
+                    continue;
+                }
+                Err(err) => return Err(err).context("Failed to read from netlink socket"),
+            };
+
+            for (what, data) in parse_proc_events(&buffer[..len]) {
+                if let Some(event) = parse_event(what, data) {
+                    events.push(event);
+                }
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Wait for the kernel to acknowledge our subscription.
+    fn wait_for_ack(&self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let mut buffer = [0u8; 8192];
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || !self.socket.poll(remaining)? {
+                bail!("Didn't receive an acknowledgement from the process connector");
+            }
+
+            let Some(len) = self.socket.read(&mut buffer)? else {
+                continue;
+            };
+
+            for (what, data) in parse_proc_events(&buffer[..len]) {
+                if what != PROC_EVENT_NONE {
+                    continue;
+                }
+                let error = read_u32(data, 0).unwrap_or_default();
+                if error != 0 {
+                    return Err(io::Error::from_raw_os_error(error as i32))
+                        .context("Process connector refused subscription");
+                }
+
+                return Ok(());
+            }
+        }
+    }
+
+    /// Send a `PROC_CN_MCAST_*` operation to the kernel.
+    fn send_operation(&self, operation: u32) -> Result<()> {
+        let total_len = NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN + 4;
+        let mut message = Vec::with_capacity(total_len);
+
+        // nlmsghdr
+        message.extend_from_slice(&(total_len as u32).to_ne_bytes());
+        message.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+        message.extend_from_slice(&0u16.to_ne_bytes());
+        message.extend_from_slice(&0u32.to_ne_bytes());
+        message.extend_from_slice(&std::process::id().to_ne_bytes());
+        // cn_msg
+        message.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+        message.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+        message.extend_from_slice(&0u32.to_ne_bytes());
+        message.extend_from_slice(&0u32.to_ne_bytes());
+        message.extend_from_slice(&4u16.to_ne_bytes());
+        message.extend_from_slice(&0u16.to_ne_bytes());
+        // The operation itself
+        message.extend_from_slice(&operation.to_ne_bytes());
+
+        self.socket
+            .send(&message)
+            .context("Failed to send operation to process connector")
+    }
+}
+
+impl Drop for ProcConnector {
+    fn drop(&mut self) {
+        // The kernel counts listeners and only emits events while there are any.
+        if let Err(err) = self.send_operation(PROC_CN_MCAST_IGNORE) {
+            trace!("Failed to unsubscribe from process connector: {err:?}");
+        }
+    }
+}
+
+/// Split a datagram into its netlink messages and return the event type and event data of each
+/// contained `proc_event`.
+fn parse_proc_events(buffer: &[u8]) -> Vec<(u32, &[u8])> {
+    parse_messages(buffer)
+        .into_iter()
+        .filter_map(|(_, payload)| {
+            // Skip the connector header. A `proc_event` starts with `what`, `cpu` and
+            // `timestamp_ns`, followed by the event data.
+            let event = payload.get(CN_MSG_HEADER_LEN..)?;
+            Some((read_u32(event, 0)?, event.get(16..)?))
+        })
+        .collect()
+}
+
+/// Convert the raw event data into an event, if it's one that we care about.
+fn parse_event(what: u32, data: &[u8]) -> Option<ConnectorEvent> {
+    // Both event types start with `process_pid` and `process_tgid`.
+    let pid = read_u32(data, 0)? as i32;
+    let tgid = read_u32(data, 4)? as i32;
+
+    match what {
+        PROC_EVENT_EXEC => Some(ConnectorEvent::Exec { pid: tgid }),
+        // Threads exiting are reported as well, but we only care about whole processes.
+        PROC_EVENT_EXIT if pid == tgid => Some(ConnectorEvent::Exit { pid: tgid }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a datagram the way the kernel sends a single `proc_event`.
+    fn datagram(what: u32, pid: i32, tgid: i32) -> Vec<u8> {
+        let len = NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN + 16 + 8;
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(&(len as u32).to_ne_bytes());
+        buffer.resize(NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN, 0);
+        buffer.extend_from_slice(&what.to_ne_bytes());
+        buffer.resize(buffer.len() + 12, 0);
+        buffer.extend_from_slice(&pid.to_ne_bytes());
+        buffer.extend_from_slice(&tgid.to_ne_bytes());
+
+        buffer
+    }
+
+    #[test]
+    fn parses_events() {
+        let mut buffer = datagram(PROC_EVENT_EXEC, 10, 10);
+        // Threads exiting should be ignored.
+        buffer.extend(datagram(PROC_EVENT_EXIT, 11, 10));
+        buffer.extend(datagram(PROC_EVENT_EXIT, 10, 10));
+
+        let events: Vec<ConnectorEvent> = parse_proc_events(&buffer)
+            .into_iter()
+            .filter_map(|(what, data)| parse_event(what, data))
+            .collect();
+
+        assert_eq!(
+            events,
+            vec![
+                ConnectorEvent::Exec { pid: 10 },
+                ConnectorEvent::Exit { pid: 10 }
+            ]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use std::{collections::HashMap, time::Instant};
+
+use anyhow::Result;
+use log::trace;
+use procfs::{
+    page_size,
+    process::{Process, all_processes},
+    ticks_per_second,
+};
+
+mod connector;
+pub mod watcher;
+
+pub use watcher::*;
+
+/// A running process with its (shortened) cmdline.
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: i32,
+    pub cmdline: String,
+}
+
+/// Get all cmdlines of currently running processes.
+pub fn get_process_cmdlines(current_user_id: u32) -> Result<Vec<String>> {
+    let processes = get_processes(current_user_id)?
+        .into_iter()
+        .map(|process| process.cmdline)
+        .collect();
+
+    Ok(processes)
+}
+
+/// Get the pid and cmdline of all currently running processes of the given user.
+pub fn get_processes(current_user_id: u32) -> Result<Vec<ProcessInfo>> {
+    let processes = all_processes()?
+        .filter_map(|process| process.ok())
+        .filter_map(|process| read_process(&process, current_user_id))
+        .collect();
+
+    Ok(processes)
+}
+
+/// Read the info of a single process.
+///
+/// Returns `None` if the process is dead, belongs to another user or if we cannot get its
+/// cmdline.
+fn read_process(process: &Process, current_user_id: u32) -> Option<ProcessInfo> {
+    // We're only interested in alive processes that belong to the current user.
+    let uid = process.uid().ok()?;
+    if !process.is_alive() || uid != current_user_id {
+        return None;
+    }
+
+    let cmdline = process.cmdline().ok()?;
+    Some(ProcessInfo {
+        pid: process.pid(),
+        cmdline: shorten_cmdline(cmdline),
+    })
+}
+
+/// Only get the first few strings which should include the name of the binary.
+fn shorten_cmdline(cmdline: Vec<String>) -> String {
+    if cmdline.len() < 6 {
+        cmdline.join(" ")
+    } else {
+        let (left, _) = cmdline.split_at(5);
+        left.join(" ")
+    }
+}
+
+/// A snapshot of the resources a single process has used up to some point in time.
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceSample {
+    /// The CPU time (user + system) in clock ticks.
+    pub cpu_ticks: u64,
+    /// The resident set size in bytes.
+    pub rss_bytes: u64,
+    pub taken_at: Instant,
+}
+
+impl ResourceSample {
+    /// Read the current resource usage of a process from `/proc/<pid>/stat`.
+    pub fn read(pid: i32) -> Result<Self> {
+        let stat = Process::new(pid)?.stat()?;
+
+        Ok(Self {
+            cpu_ticks: stat.utime + stat.stime,
+            rss_bytes: stat.rss * page_size(),
+            taken_at: Instant::now(),
+        })
+    }
+}
+
+/// The resource utilisation of a process between two samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceUsage {
+    /// The CPU utilisation in percent of a single core.
+    /// Multi-threaded processes may exceed 100%.
+    pub cpu_percent: f64,
+    /// The resident set size in bytes at the time of the latest sample.
+    pub rss_bytes: u64,
+}
+
+impl ResourceUsage {
+    /// Compute the utilisation between two samples of the same process.
+    pub fn between(
+        previous: &ResourceSample,
+        current: &ResourceSample,
+        ticks_per_second: u64,
+    ) -> Self {
+        let elapsed = current
+            .taken_at
+            .saturating_duration_since(previous.taken_at)
+            .as_secs_f64();
+        // Clamp to `0` in case the pid has been reused by a new process in the meantime.
+        let ticks = current.cpu_ticks.saturating_sub(previous.cpu_ticks);
+
+        let cpu_percent = if elapsed > 0.0 && ticks_per_second > 0 {
+            ticks as f64 / ticks_per_second as f64 / elapsed * 100.0
+        } else {
+            0.0
+        };
+
+        Self {
+            cpu_percent,
+            rss_bytes: current.rss_bytes,
+        }
+    }
+}
+
+/// Sample the resource usage of a set of processes across multiple ticks.
+///
+/// Each call to [`ResourceSampler::sample`] computes the utilisation since the previous call.
+#[derive(Debug)]
+pub struct ResourceSampler {
+    ticks_per_second: u64,
+    samples: HashMap<i32, ResourceSample>,
+}
+
+impl Default for ResourceSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourceSampler {
+    pub fn new() -> Self {
+        Self {
+            ticks_per_second: ticks_per_second(),
+            samples: HashMap::new(),
+        }
+    }
+
+    /// Take a new sample for all given pids.
+    ///
+    /// Returns the utilisation for every pid that has also been sampled during the last tick.
+    /// Pids that are seen for the first time or that vanished in the meantime aren't included.
+    /// Samples of pids that aren't passed anymore are forgotten.
+    pub fn sample(&mut self, pids: &[i32]) -> HashMap<i32, ResourceUsage> {
+        let mut usages = HashMap::new();
+        let mut samples = HashMap::new();
+
+        for pid in pids {
+            let current = match ResourceSample::read(*pid) {
+                Ok(sample) => sample,
+                Err(err) => {
+                    trace!("Failed to sample process {pid}: {err:?}");
+                    continue;
+                }
+            };
+
+            if let Some(previous) = self.samples.get(pid) {
+                let usage = ResourceUsage::between(previous, &current, self.ticks_per_second);
+                usages.insert(*pid, usage);
+            }
+
+            samples.insert(*pid, current);
+        }
+
+        self.samples = samples;
+
+        usages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn sample(cpu_ticks: u64, taken_at: Instant) -> ResourceSample {
+        ResourceSample {
+            cpu_ticks,
+            rss_bytes: 4096,
+            taken_at,
+        }
+    }
+
+    #[test]
+    fn computes_cpu_percentage() {
+        let start = Instant::now();
+        let previous = sample(100, start);
+        let current = sample(150, start + Duration::from_secs(2));
+
+        // 50 ticks at 100 ticks per second over two seconds is a quarter of a core.
+        let usage = ResourceUsage::between(&previous, &current, 100);
+        assert_eq!(usage.cpu_percent, 25.0);
+        assert_eq!(usage.rss_bytes, 4096);
+    }
+
+    #[test]
+    fn handles_reused_pids_and_zero_time() {
+        let start = Instant::now();
+
+        // The pid got reused by a process that used less CPU time.
+        let usage = ResourceUsage::between(
+            &sample(500, start),
+            &sample(10, start + Duration::from_secs(1)),
+            100,
+        );
+        assert_eq!(usage.cpu_percent, 0.0);
+
+        // No time has passed between both samples.
+        let usage = ResourceUsage::between(&sample(0, start), &sample(10, start), 100);
+        assert_eq!(usage.cpu_percent, 0.0);
+    }
+
+    #[test]
+    fn samples_own_process() {
+        let pid = std::process::id() as i32;
+        let mut sampler = ResourceSampler::new();
+
+        // The first sample has nothing to compare to.
+        assert!(sampler.sample(&[pid]).is_empty());
+
+        let usages = sampler.sample(&[pid]);
+        let usage = usages
+            .get(&pid)
+            .expect("Expected a usage for our own process");
+        assert!(usage.rss_bytes > 0);
+    }
+}
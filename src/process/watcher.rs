@@ -0,0 +1,264 @@
+//! Watch for processes of a user being started or exiting.
+//!
+//! If permitted, the netlink process connector is used, which notifies us about every `exec` and
+//! `exit` as soon as it happens. Otherwise, we fall back to periodically diffing `/proc`.
+use std::{
+    collections::{HashMap, HashSet},
+    thread::sleep,
+    time::Duration,
+};
+
+use anyhow::Result;
+use log::{debug, info};
+use procfs::process::{Process, all_processes};
+
+use super::{
+    ProcessInfo,
+    connector::{ConnectorEvent, ProcConnector},
+    read_process,
+};
+
+#[derive(Debug, Clone)]
+pub enum ProcessEvent {
+    Started(ProcessInfo),
+    /// A known process changed its cmdline, e.g. by `exec`ing another binary or by rewriting its
+    /// argv. It's still the same process though.
+    Changed(ProcessInfo),
+    Exited(ProcessInfo),
+}
+
+pub struct ProcessWatcher {
+    current_user_id: u32,
+    /// The connector socket, if we're allowed to use it.
+    connector: Option<ProcConnector>,
+    /// The interval at which `/proc` is scanned if we cannot use the connector.
+    poll_interval: Duration,
+    /// All processes we've reported as started and that haven't exited yet.
+    known: HashMap<i32, ProcessInfo>,
+    initialized: bool,
+}
+
+impl ProcessWatcher {
+    /// Create a new watcher for all processes of the given user.
+    ///
+    /// This tries to subscribe to the process connector and falls back to polling `/proc` at the
+    /// given interval if that's not possible.
+    pub fn new(current_user_id: u32, poll_interval: Duration) -> Self {
+        let mut watcher = Self::polling(current_user_id, poll_interval);
+
+        match ProcConnector::new() {
+            Ok(connector) => {
+                info!("Watching processes via the netlink process connector");
+                watcher.connector = Some(connector);
+            }
+            Err(err) => {
+                info!("Cannot use the process connector, falling back to polling: {err:#}");
+            }
+        }
+
+        watcher
+    }
+
+    /// Create a new watcher that always polls `/proc` at the given interval.
+    pub fn polling(current_user_id: u32, poll_interval: Duration) -> Self {
+        Self {
+            current_user_id,
+            connector: None,
+            poll_interval,
+            known: HashMap::new(),
+            initialized: false,
+        }
+    }
+
+    /// Whether we get notified by the kernel or have to poll.
+    pub fn is_event_driven(&self) -> bool {
+        self.connector.is_some()
+    }
+
+    /// Wait up to `timeout` for processes to be started or to exit.
+    ///
+    /// The first call immediately reports all processes that're already running as `Started`.
+    /// An empty list is returned if nothing happened until the timeout.
+    pub fn wait(&mut self, timeout: Duration) -> Result<Vec<ProcessEvent>> {
+        if !self.initialized {
+            self.initialized = true;
+            return Ok(self.rescan());
+        }
+
+        let Some(connector) = &self.connector else {
+            sleep(timeout.min(self.poll_interval));
+            return Ok(self.rescan());
+        };
+
+        let mut events = Vec::new();
+        for event in connector.receive(timeout)? {
+            match event {
+                ConnectorEvent::Exec { pid } => {
+                    let Some(info) = Process::new(pid)
+                        .ok()
+                        .and_then(|process| read_process(&process, self.current_user_id))
+                    else {
+                        continue;
+                    };
+
+                    // Launchers and wrapper scripts often `exec` the actual binary, which doesn't
+                    // make it a new process.
+                    match self.known.insert(pid, info.clone()) {
+                        Some(_) => events.push(ProcessEvent::Changed(info)),
+                        None => events.push(ProcessEvent::Started(info)),
+                    }
+                }
+                ConnectorEvent::Exit { pid } => {
+                    if let Some(info) = self.known.remove(&pid) {
+                        events.push(ProcessEvent::Exited(info));
+                    }
+                }
+                ConnectorEvent::Overrun => {
+                    debug!("Lost process events, rescanning /proc");
+                    events.extend(self.rescan());
+                }
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Read the cmdline of all known processes that match the filter again and report those that
+    /// changed.
+    ///
+    /// Some processes rewrite their argv after they've been started, which we wouldn't notice
+    /// otherwise. Processes that exec another binary are only noticed this way when polling.
+    pub fn refresh_cmdlines<F: Fn(&ProcessInfo) -> bool>(
+        &mut self,
+        filter: F,
+    ) -> Vec<ProcessEvent> {
+        let mut events = Vec::new();
+        for (pid, known) in self.known.iter_mut() {
+            if !filter(known) {
+                continue;
+            }
+
+            let Some(info) = Process::new(*pid)
+                .ok()
+                .and_then(|process| read_process(&process, self.current_user_id))
+            else {
+                continue;
+            };
+            if info.cmdline != known.cmdline {
+                *known = info.clone();
+                events.push(ProcessEvent::Changed(info));
+            }
+        }
+
+        events
+    }
+
+    /// Scan `/proc` and diff it against the processes we already know about.
+    ///
+    /// Only processes we haven't seen yet are read in full.
+    fn rescan(&mut self) -> Vec<ProcessEvent> {
+        let mut events = Vec::new();
+        let processes = match all_processes() {
+            Ok(processes) => processes,
+            Err(err) => {
+                debug!("Failed to read /proc: {err:?}");
+                return events;
+            }
+        };
+
+        let mut seen = HashSet::new();
+        for process in processes.filter_map(|process| process.ok()) {
+            let pid = process.pid();
+            if self.known.contains_key(&pid) {
+                seen.insert(pid);
+                continue;
+            }
+
+            if let Some(info) = read_process(&process, self.current_user_id) {
+                seen.insert(pid);
+                self.known.insert(pid, info.clone());
+                events.push(ProcessEvent::Started(info));
+            }
+        }
+
+        let exited: Vec<i32> = self
+            .known
+            .keys()
+            .filter(|pid| !seen.contains(pid))
+            .copied()
+            .collect();
+        for pid in exited {
+            if let Some(info) = self.known.remove(&pid) {
+                events.push(ProcessEvent::Exited(info));
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    #[test]
+    fn detects_started_and_exited_processes() -> Result<()> {
+        let mut watcher = ProcessWatcher::polling(users::get_current_uid(), Duration::ZERO);
+
+        // The initial scan reports our own process.
+        let events = watcher.wait(Duration::ZERO)?;
+        let own_pid = std::process::id() as i32;
+        assert!(
+            events
+                .iter()
+                .any(|event| matches!(event, ProcessEvent::Started(info) if info.pid == own_pid))
+        );
+
+        let mut child = Command::new("sleep").arg("30").spawn()?;
+        let child_pid = child.id() as i32;
+        let events = watcher.wait(Duration::ZERO)?;
+        assert!(
+            events
+                .iter()
+                .any(|event| matches!(event, ProcessEvent::Started(info) if info.pid == child_pid))
+        );
+
+        child.kill()?;
+        child.wait()?;
+        let events = watcher.wait(Duration::ZERO)?;
+        assert!(
+            events
+                .iter()
+                .any(|event| matches!(event, ProcessEvent::Exited(info) if info.pid == child_pid))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn reports_exec_as_change() -> Result<()> {
+        let mut watcher = ProcessWatcher::polling(users::get_current_uid(), Duration::ZERO);
+        watcher.wait(Duration::ZERO)?;
+
+        // A wrapper script that execs the actual binary.
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 0.2; exec sleep 30"])
+            .spawn()?;
+        let child_pid = child.id() as i32;
+        watcher.wait(Duration::ZERO)?;
+        std::thread::sleep(Duration::from_millis(500));
+
+        // The process is still the same, so it's neither reported as exited nor as started.
+        let events = watcher.refresh_cmdlines(|info| info.pid == child_pid);
+        child.kill()?;
+        child.wait()?;
+        assert!(matches!(
+            events.as_slice(),
+            [ProcessEvent::Changed(info)] if info.pid == child_pid && info.cmdline == "sleep 30"
+        ));
+
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
+use std::{iter::Chain, ops::Index, slice};
+
 use anyhow::{Result, bail};
 
+/// Iterator over all elements of a [Ring], starting at the cursor.
+pub type Iter<'a, T> = Chain<slice::Iter<'a, T>, slice::Iter<'a, T>>;
+
 #[derive(Debug)]
 pub struct Ring<T> {
     cursor: usize,
@@ -18,39 +23,74 @@ impl<T> Ring<T> {
         Ok(Ring { cursor: 0, data })
     }
 
+    /// The amount of elements in the ring.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Always `false`, as rings cannot be empty.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The index of the current entry.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
     /// Get the current entry in the ring.
-    /// This panics if the ring is empty.
     pub fn get(&mut self) -> &T {
         &self.data[self.cursor]
     }
 
     /// Move the cursor to the next element and return the element.
-    /// This panics if the ring is empty.
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> &T {
-        // If we're at the end of the array, move to the start.
-        if self.data.get(self.cursor + 1).is_none() {
-            self.cursor = 0;
-        } else {
-            self.cursor += 1;
-        }
+        self.cursor = self.offset(1);
 
         &self.data[self.cursor]
     }
 
     /// Move the cursor to the previous element and return the element.
-    /// This panics if the ring is empty.
     pub fn prev(&mut self) -> &T {
-        // If we're at the start of the array, move to the end.
-        if self.cursor == 0 {
-            self.cursor = self.data.len() - 1;
-        } else {
-            self.cursor -= 1;
-        }
+        self.cursor = self.offset(self.data.len() - 1);
 
         &self.data[self.cursor]
     }
 
+    /// Move the cursor to the next element that matches the given predicate.
+    ///
+    /// All other elements are checked before the current one. I.e. the current element is only
+    /// returned if it's the only matching element.
+    /// If none is found, do nothing and return `None`.
+    pub fn next_matching<Predicate>(&mut self, predicate: Predicate) -> Option<&T>
+    where
+        Predicate: Fn(&T) -> bool,
+    {
+        let index = (1..=self.data.len())
+            .map(|step| self.offset(step))
+            .find(|index| predicate(&self.data[*index]))?;
+
+        self.cursor = index;
+        Some(&self.data[self.cursor])
+    }
+
+    /// Move the cursor to the previous element that matches the given predicate.
+    ///
+    /// Behaves just like [`Ring::next_matching`], but in the other direction.
+    pub fn prev_matching<Predicate>(&mut self, predicate: Predicate) -> Option<&T>
+    where
+        Predicate: Fn(&T) -> bool,
+    {
+        let len = self.data.len();
+        let index = (1..=len)
+            .map(|step| self.offset(len - step))
+            .find(|index| predicate(&self.data[*index]))?;
+
+        self.cursor = index;
+        Some(&self.data[self.cursor])
+    }
+
     /// Move the cursor to the first element that matches the given criteria.
     /// If none is found, do nothing and return `None`.
     pub fn find<Filter>(&mut self, find: Filter) -> Option<&T>
@@ -62,4 +102,150 @@ impl<T> Ring<T> {
         self.cursor = index;
         Some(&self.data[self.cursor])
     }
+
+    /// Iterate over all elements once, starting at the cursor, without moving it.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.data[self.cursor..]
+            .iter()
+            .chain(self.data[..self.cursor].iter())
+    }
+
+    /// The index of the element that's `steps` elements after the cursor.
+    fn offset(&self, steps: usize) -> usize {
+        (self.cursor + steps) % self.data.len()
+    }
+}
+
+/// Access elements by their absolute index, independent of the cursor.
+impl<T> Index<usize> for Ring<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.data[index]
+    }
+}
+
+/// Iterate over all elements once, starting at the cursor.
+impl<T> IntoIterator for Ring<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        self.data.rotate_left(self.cursor);
+        self.data.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Ring<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build rings of all sizes up to 8 with every possible cursor position.
+    fn all_rings() -> impl Iterator<Item = Ring<usize>> {
+        (1..=8).flat_map(|len| {
+            (0..len).map(move |cursor| {
+                let mut ring = Ring::new((0..len).collect()).unwrap();
+                ring.cursor = cursor;
+                ring
+            })
+        })
+    }
+
+    #[test]
+    fn empty_ring() {
+        assert!(Ring::<usize>::new(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn next_and_prev_are_inverse() {
+        for mut ring in all_rings() {
+            let start = ring.cursor();
+            ring.next();
+            assert_eq!(*ring.prev(), start);
+            ring.prev();
+            assert_eq!(*ring.next(), start);
+
+            // Going around the ring once ends up at the start again.
+            for _ in 0..ring.len() {
+                ring.next();
+            }
+            assert_eq!(ring.cursor(), start);
+        }
+    }
+
+    #[test]
+    fn matching_without_predicate_equals_plain_movement() {
+        for mut ring in all_rings() {
+            let start = ring.cursor();
+            let next = (start + 1) % ring.len();
+            assert_eq!(ring.next_matching(|_| true), Some(&next));
+            assert_eq!(ring.prev_matching(|_| true), Some(&start));
+        }
+    }
+
+    #[test]
+    fn matching_skips_elements() {
+        for mut ring in all_rings() {
+            let start = ring.cursor();
+            let len = ring.len();
+
+            // Only even elements match. Walk around the ring and check that we hit all of them.
+            let mut visited = Vec::new();
+            for _ in 0..len {
+                let found = *ring.next_matching(|value| value % 2 == 0).unwrap();
+                assert_eq!(found % 2, 0);
+                visited.push(found);
+            }
+            visited.sort();
+            visited.dedup();
+            assert_eq!(visited, (0..len).step_by(2).collect::<Vec<_>>());
+
+            // The previous match is the closest even element before the cursor.
+            ring.cursor = start;
+            let expected = (1..=len)
+                .map(|step| (start + len - step) % len)
+                .find(|value| value % 2 == 0);
+            assert_eq!(
+                ring.prev_matching(|value| value % 2 == 0).copied(),
+                expected
+            );
+
+            // The cursor stays where it is if nothing matches.
+            ring.cursor = start;
+            assert_eq!(ring.next_matching(|value| *value >= len), None);
+            assert_eq!(ring.prev_matching(|value| *value >= len), None);
+            assert_eq!(ring.cursor(), start);
+
+            // The current element is returned if it's the only matching one.
+            assert_eq!(ring.next_matching(|value| *value == start), Some(&start));
+        }
+    }
+
+    #[test]
+    fn iteration_starts_at_cursor() {
+        for ring in all_rings() {
+            let cursor = ring.cursor();
+            let len = ring.len();
+            let expected: Vec<usize> = (0..len).map(|step| (cursor + step) % len).collect();
+
+            assert_eq!(ring.iter().copied().collect::<Vec<_>>(), expected);
+            assert_eq!((&ring).into_iter().copied().collect::<Vec<_>>(), expected);
+            // Iterating doesn't move the cursor.
+            assert_eq!(ring.cursor(), cursor);
+            for index in 0..len {
+                assert_eq!(ring[index], index);
+            }
+
+            assert_eq!(ring.into_iter().collect::<Vec<_>>(), expected);
+        }
+    }
 }
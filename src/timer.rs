@@ -134,6 +134,12 @@ impl<T: Clone> PhaseTimer<T> {
         self.last_check_time = None;
     }
 
+    /// Don't count the given time towards the elapsed time, e.g. while the timed activity is
+    /// paused.
+    pub fn pause(&mut self, duration: Duration) {
+        self.start_time += duration;
+    }
+
     /// Check if a phase should trigger right now.
     ///
     /// If so, the respective action  will be returned.
@@ -385,6 +391,16 @@ mod tests {
         assert_eq!(action, Some(TestAction::Reminder));
     }
 
+    #[test]
+    fn paused_time_doesnt_count() {
+        let phases = vec![Phase::one_time(90, TestAction::Initial)];
+        let mut timer = PhaseTimer::new(phases);
+        timer.start_time = Utc::now() - Duration::minutes(60);
+
+        timer.pause(Duration::minutes(20));
+        assert_eq!(timer.elapsed_minutes(), 40);
+    }
+
     #[test]
     fn delayed_recurring_phase() {
         // Test the dehn-polizei scenario: one-time at 90min, delayed recurring starts at 90min but